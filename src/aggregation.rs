@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use crate::evaluator::Variable;
+use crate::projection::Projection;
+use crate::tokenizer::Token;
+
+pub enum Function {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+pub struct Aggregate {
+    label: String,
+    function: Function,
+    variable: Option<Variable<f64>>,
+}
+
+impl Aggregate {
+    pub fn new(label: &str, function: Function, variable: Option<Variable<f64>>) -> Self {
+        Aggregate {
+            label: label.to_string(),
+            function,
+            variable,
+        }
+    }
+}
+
+pub struct Key {
+    label: String,
+    projection: Projection,
+}
+
+impl Key {
+    pub fn new(label: &str, projection: Projection) -> Self {
+        Key {
+            label: label.to_string(),
+            projection,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Accumulator {
+    lines: usize,
+    values: usize,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Accumulator {
+    fn new() -> Self {
+        Accumulator {
+            lines: 0,
+            values: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn add(&mut self, value: Option<f64>) {
+        self.lines += 1;
+        if let Some(value) = value {
+            self.values += 1;
+            self.sum += value;
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+    }
+
+    fn result(&self, function: &Function) -> String {
+        match (function, self.values) {
+            (Function::Count, _) => self.lines.to_string(),
+            (_, 0) => String::new(),
+            (Function::Sum, _) => self.sum.to_string(),
+            (Function::Min, _) => self.min.to_string(),
+            (Function::Max, _) => self.max.to_string(),
+            (Function::Avg, _) => (self.sum / self.values as f64).to_string(),
+        }
+    }
+}
+
+// streaming aggregation of the first value of each variable per line, grouped by the first value of each key
+pub struct Aggregation {
+    aggregates: Vec<Aggregate>,
+    keys: Vec<Key>,
+    groups: Vec<(Vec<String>, Vec<Accumulator>)>,
+    indices: HashMap<Vec<String>, usize>,
+}
+
+impl Aggregation {
+    pub fn new(aggregates: Vec<Aggregate>, keys: Vec<Key>) -> Self {
+        Aggregation {
+            aggregates,
+            keys,
+            groups: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    // lines without a value for each key are not aggregated
+    pub fn add(&mut self, tokens: &[Token]) {
+        let mut group = Vec::new();
+        for key in &self.keys {
+            match key.projection.values(tokens).into_iter().next() {
+                Some(value) => group.push(value),
+                None => return,
+            }
+        }
+
+        let index = match self.indices.get(&group) {
+            Some(index) => *index,
+            None => {
+                self.indices.insert(group.clone(), self.groups.len());
+                self.groups
+                    .push((group, vec![Accumulator::new(); self.aggregates.len()]));
+                self.groups.len() - 1
+            }
+        };
+
+        let accumulators = &mut self.groups[index].1;
+        for (aggregate, accumulator) in self.aggregates.iter().zip(accumulators.iter_mut()) {
+            let value = aggregate
+                .variable
+                .as_ref()
+                .and_then(|variable| variable.terms(tokens).into_iter().next())
+                .map(|term| term.value);
+            accumulator.add(value);
+        }
+    }
+
+    pub fn header(&self) -> Vec<String> {
+        let keys = self.keys.iter().map(|key| key.label.clone());
+        let aggregates = self.aggregates.iter().map(|aggregate| aggregate.label.clone());
+        keys.chain(aggregates).collect()
+    }
+
+    // one row per group in the order the groups were first seen
+    pub fn rows(&self) -> Vec<Vec<String>> {
+        self.groups
+            .iter()
+            .map(|(group, accumulators)| {
+                let results = self
+                    .aggregates
+                    .iter()
+                    .zip(accumulators)
+                    .map(|(aggregate, accumulator)| accumulator.result(&aggregate.function));
+                group.iter().cloned().chain(results).collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod aggregation_tests {
+    use crate::expression::expression;
+    use crate::filter::test_utils;
+    use crate::tokenizer::{Separators, Tokenizer};
+
+    #[test]
+    fn aggregate_groups() {
+        // setup
+        let tokenizer = Tokenizer::new(Separators::new(vec![" "]).unwrap()).unwrap();
+        let formats = test_utils::default_formats();
+        let mut aggregation = expression::aggregation(
+            "count(), sum($integer), min($integer), max($integer), avg($integer) by $1",
+            &formats,
+        )
+        .unwrap();
+
+        // exercise
+        for line in ["a 1", "b 2", "a 5 7", "a x", "b"] {
+            aggregation.add(&tokenizer.tokens(line));
+        }
+
+        // verify
+        assert_eq!(
+            aggregation.header(),
+            vec![
+                "$1",
+                "count()",
+                "sum($integer)",
+                "min($integer)",
+                "max($integer)",
+                "avg($integer)"
+            ]
+        );
+        assert_eq!(
+            aggregation.rows(),
+            vec![vec!["a", "3", "6", "1", "5", "3"], vec!["b", "2", "2", "2", "2", "2"]]
+        );
+    }
+
+    #[test]
+    fn aggregate_without_values() {
+        // setup
+        let tokenizer = Tokenizer::new(Separators::new(vec![" "]).unwrap()).unwrap();
+        let formats = test_utils::default_formats();
+        let mut aggregation = expression::aggregation("count(),sum($float) by $ipv4Address", &formats).unwrap();
+
+        // exercise
+        for line in ["10.0.0.1 a", "b", "10.0.0.1 1.5"] {
+            aggregation.add(&tokenizer.tokens(line));
+        }
+
+        // verify
+        assert_eq!(aggregation.rows(), vec![vec!["10.0.0.1", "2", "1.5"]]);
+    }
+}
@@ -1,16 +1,24 @@
 use crate::expression::Validator;
-use crate::filter::{Formats, Mode, Settings};
+use crate::filter::{BinaryFiles, Format, Formats, Mode, Settings};
+use crate::filter::{CSV, JSON, NDJSON, TEXT, TSV};
 use crate::filter::{DATE_FORMAT, DATE_TIME_FORMAT, LOCAL_DATE_TIME_FORMAT, TIME_FORMAT};
-use crate::filter::{FILTER, FILTER_HIGHLIGHT, HIGHLIGHT};
+use crate::filter::{FAIL, LOSSY, SKIP};
+use crate::filter::{FILTER, FILTER_HIGHLIGHT, HIGHLIGHT, ONLY_MATCHING, REJECT};
+use crate::input::{Input, Inputs};
+use crate::record::RecordSeparator;
 use crate::tokenizer::Separators;
 use crate::tokenizer::{SEPARATORS, WHITESPACES};
-use anyhow::{Context, Error};
+use anyhow::{anyhow, Context, Error};
 use chrono::format::{strftime::StrftimeItems, Item};
-use clap::{App, Arg};
+use chrono::{DateTime, Local};
+use clap::{App, Arg, Values};
+use encoding_rs::Encoding;
+use regex::bytes::Regex;
 use std::collections::HashSet;
 use std::fs::File;
-use std::io::{stdin, stdout, Read, Write};
+use std::io::{stdout, Write};
 use std::str::FromStr;
+use std::time::Duration;
 
 const NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -19,10 +27,16 @@ const EXPRESSION_HELP: &str = r#"Filter expression applied to tokens found on ea
 
 SYNTAX
 An <expression> can be a single <condition> or multiple <condition>s combined
-with <operator>s. In complex <expression>s parenthesis can be used to group
-<condition>s. Each <condition> compares a typed <variable> with a literal
-<value> using a <comparator>. A <function> can be applied to some typed
-<variable>s before comparison with the literal <value>.
+with <operator>s. A <condition> can be negated using 'not' or '!'. In complex
+<expression>s parenthesis can be used to group <condition>s; without
+parenthesis 'not' binds stronger than <correlator>s, which bind stronger than
+'and', which binds stronger than 'or'. Each <condition> compares a typed
+<variable> with a literal <value> using a <comparator>. A <function> can be
+applied to some typed <variable>s before comparison with the literal <value>.
+
+Whitespace between lexemes is optional; only word <operator>s and
+<comperator>s such as 'and', 'not', or 'contains' must be separated from
+adjacent words.
 
 The supported <operator>s, <comparator>s, <variable>s, and <function>s, and
 how an <expression> is constructed using <condition>s is shown in EBNF below.
@@ -30,28 +44,43 @@ how an <expression> is constructed using <condition>s is shown in EBNF below.
 <expression>           ::=  <conditions>
 <conditions>           ::=  <condition> |
                             <conditions> <operator> <conditions> |
+                            <conditions> <correlator> <conditions> |
+                            <negation> <conditions> |
+                            <quantifier> |
                             ( <conditions> )
 <operator>             ::=  and | or
+<negation>             ::=  not | !
+<correlator>           ::=  followed-by | within <distance> | same-token
+<quantifier>           ::=  all(<condition>) |
+                            none(<condition>) | none(<variable>) |
+                            count(<condition>) <basic-comperator> <count> |
+                            count(<variable>) <basic-comperator> <count>
 <condition>            ::=  <variable> <comperator> <value> |
                             <function>(<variable>) <comperator> <value>
 <comperator>           ::=  <basic-comperator> | <extended-comperator>
 <basic-comperator>     ::=  == | != | > | >= | < | <=
 <extended-comperator>  ::=  contains | starts-with | ends-with |
-                            in | not in | matches
+                            in | not in | matches |
+                            =~ | !~ | matches-regex
 <function>             ::=  port | ip
-<variable>             ::=  $integer | $float | $id | $date | $time |
-                            $dateTime | $localDateTime | $ipAddress |
+<variable>             ::=  <type> | <type><selector> | $<field>
+<selector>             ::=  @<field> | [<occurrence>]
+<type>                 ::=  $integer | $float | $id | $text | $word |
+                            $date | $time |
+                            $dateTime | $localDateTime | $timestamp |
+                            $ipAddress |
                             $ipv4Address | $ipv6Address | $ipSocketAddress |
                             $ipv4SocketAddress | $ipv6SocketAddress |
                             $ipNetwork | $ipv4Network | $ipv6Network |
                             $semanticVersion
-<value>                ::=  <integer> | <float> | <id> | <date> | <time> |
-                            <dateTime> | <localDateTime> | <ipAddress> |
+<value>                ::=  <integer> | <float> | <id> | <word> | <date> | <time> |
+                            <dateTime> | <localDateTime> | <timestamp> |
+                            <ipAddress> |
                             <ipv4Address> | <ipv6Address> | <ipSocketAddress> |
                             <ipv4SocketAddress> | <ipv6SocketAddress> |
                             <ipNetwork> | <ipv4Network> | <ipv6Network> |
                             <semanticVersion> | <semanticVersionRequirement> |
-                            <port>
+                            <port> | <string> | <regex>
 
 The expected format of the literal <value> in a <condition> depends on the
 <variable> type and the <comperator> being used. Which <comperator> can be
@@ -60,6 +89,30 @@ types whereas <extended-comperator>s are supported only for some types.
 
 In <condition>s <function>s can be applied only to some <variable> types.
 
+A <string> is enclosed in double or single quotes and may contain separators,
+whitespace, and operators; escape sequences such as \" and \' are supported.
+
+A <timestamp> is recognized in RFC 3339, RFC 2822, syslog, Apache/NCSA common
+log, ISO week date, and Unix epoch seconds or milliseconds format; $timestamp
+values and <timestamp>s are compared as instants in UTC.
+
+A date or time <value> can also be relative: 'now', 'today', or 'yesterday'
+optionally followed by offsets, or offsets alone, e.g. 'now-1h', 'today+8h',
+or '-15m', with units s, m, h, d, and w. Relative <value>s are resolved once
+against the current time or the time given with --now.
+
+A <quantifier> changes how many tokens need to satisfy a <condition>: 'all'
+requires at least one and only matching tokens, 'none' requires no matching
+token, and 'count' compares the number of matching tokens with <count>.
+
+A <correlator> constrains the fields of the tokens matched on both sides:
+'followed-by' requires the right token in the field immediately after the left
+token, 'within <distance>' requires both tokens at most <distance> fields
+apart, and 'same-token' requires both sides to match the same token.
+
+A <variable> can be restricted to a <field> or to an <occurrence>; fields are
+the tokens on a line which are not separators, counted starting at 1.
+
 Please refer to https://github.com/qpanda/semfilter/blob/master/EXPRESSION.md
 for more details on the expression syntax.
 
@@ -76,10 +129,37 @@ EXAMPLES
    Match all lines containing an id value equal to 'ESTABLISHED' and a IPv4
    socket address which has an IPv4 address in IPv4 network 193.32.160.0/24
 
+'$dateTime > now-1h'
+   Match all lines containing a dateTime value later than one hour ago
+
+'$id =~ "^ERR[0-9]+$" and $integer > 500'
+   Match all lines containing an id value matching the regular expression
+   ^ERR[0-9]+$ and an integer value greater than 500
+
+'$text == "/var/log/syslog" and $integer >= 500'
+   Match all lines containing the text value '/var/log/syslog' and an integer
+   value greater than or equal to 500
+
+'$ipv4Address in 10.0.0.0/8 followed-by port($ipv4SocketAddress) == 22'
+   Match all lines containing an IPv4 address in IPv4 network 10.0.0.0/8
+   which is immediately followed by an IPv4 socket address with port 22
+
+'all($integer < 100) and none($ipv6Address)'
+   Match all lines on which every integer value is less than 100 and which
+   contain no IPv6 address
+
+'not $id == root and $integer > 5 and $float < 1.0'
+   Match all lines not containing an id value equal to 'root' but containing
+   an integer value greater than 5 and a float value less than 1.0
+
+'$1 == tcp and $integer@2 > 0'
+   Match all lines with 'tcp' in the first field and an integer value greater
+   than 0 in the second field
+
 "#;
 
 pub struct Arguments {
-    pub input: Box<dyn Read>,
+    pub inputs: Vec<Result<Input, Error>>,
     pub output: Box<dyn Write>,
     pub expression: String,
     pub separators: Separators,
@@ -89,15 +169,37 @@ pub struct Arguments {
 impl Arguments {
     pub fn parse() -> Result<Self, Error> {
         let input_argument = "input";
+        let input_files_argument = "input-files";
+        let recursive_argument = "recursive";
         let output_argument = "output";
         let add_separator_argument = "add-separator";
         let remove_separator_argument = "remove-separator";
         let mode_argument = "mode";
         let count_argument = "count";
+        let invert_match_argument = "invert-match";
+        let after_context_argument = "after-context";
+        let before_context_argument = "before-context";
+        let context_argument = "context";
+        let line_number_argument = "line-number";
+        let byte_offset_argument = "byte-offset";
+        let only_matching_argument = "only-matching";
+        let delimiter_argument = "delimiter";
+        let output_format_argument = "output-format";
+        let print_argument = "print";
+        let print_all_argument = "print-all";
+        let aggregate_argument = "aggregate";
+        let follow_argument = "follow";
+        let count_interval_argument = "count-interval";
+        let no_decompress_argument = "no-decompress";
+        let encoding_argument = "encoding";
+        let binary_files_argument = "binary-files";
+        let null_data_argument = "null-data";
+        let record_separator_argument = "record-separator";
         let date_format_argument = "date-format";
         let time_format_argument = "time-format";
         let date_time_format_argument = "date-time-format";
         let local_date_time_format_argument = "local-date-time-format";
+        let now_argument = "now";
         let expression_argument = "expression";
 
         let semfilter_command = App::new(NAME)
@@ -108,14 +210,139 @@ impl Arguments {
                     .short("c")
                     .long("count")
                     .takes_value(false)
-                    .help("Print processed and matched line count"),
+                    .help("Print processed, matched, and rejected line count"),
+            )
+            .arg(
+                Arg::with_name(follow_argument)
+                    .short("f")
+                    .long("follow")
+                    .takes_value(false)
+                    .help("Keep reading lines appended to the input-file, re-open it if it is rotated or truncated (like 'tail -F')"),
+            )
+            .arg(
+                Arg::with_name(count_interval_argument)
+                    .long("count-interval")
+                    .value_name("seconds")
+                    .validator(Arguments::validate_number)
+                    .requires_all(&[count_argument, follow_argument])
+                    .help("Print line counts every number of seconds while following (line counts are printed on SIGUSR1 as well)"),
+            )
+            .arg(
+                Arg::with_name(no_decompress_argument)
+                    .long("no-decompress")
+                    .takes_value(false)
+                    .help("Do not decompress gzip, zstd, bzip2, or xz compressed input-files"),
+            )
+            .arg(
+                Arg::with_name(encoding_argument)
+                    .long("encoding")
+                    .value_name("encoding")
+                    .default_value("utf-8")
+                    .validator(Arguments::validate_encoding)
+                    .help("Encoding of input-files, e.g. 'latin1' or 'windows-1252'"),
+            )
+            .arg(
+                Arg::with_name(binary_files_argument)
+                    .long("binary-files")
+                    .value_name("policy")
+                    .default_value(SKIP)
+                    .possible_values(&[SKIP, LOSSY, FAIL])
+                    .help("Policy for input-files with NUL bytes or bytes invalid in the encoding")
+                    .long_help("Policy for input-files with NUL bytes or bytes invalid in the encoding; policy 'skip' does not print lines of an input-file which starts with binary data, or stops printing them once binary data is found, and prints 'Binary file matches' if any of its lines match instead (like grep), policy 'lossy' replaces invalid bytes with U+FFFD, policy 'fail' aborts with an error\n")
+                    .next_line_help(true),
+            )
+            .arg(
+                Arg::with_name(null_data_argument)
+                    .short("z")
+                    .long("null-data")
+                    .takes_value(false)
+                    .conflicts_with(record_separator_argument)
+                    .help("Input and output records are separated by NUL bytes instead of newlines"),
+            )
+            .arg(
+                Arg::with_name(record_separator_argument)
+                    .long("record-separator")
+                    .value_name("regex")
+                    .validator(Arguments::validate_record_separator)
+                    .help("Input records are separated by matches of the regex instead of newlines")
+                    .long_help("Input records are separated by matches of the regex instead of newlines; each output record is followed by the separator which followed it in the input\n")
+                    .next_line_help(true),
+            )
+            .arg(
+                Arg::with_name(invert_match_argument)
+                    .short("v")
+                    .long("invert-match")
+                    .takes_value(false)
+                    .conflicts_with(mode_argument)
+                    .help("Print only lines which do not match (same as mode 'reject')"),
+            )
+            .arg(
+                Arg::with_name(line_number_argument)
+                    .short("n")
+                    .long("line-number")
+                    .takes_value(false)
+                    .help("Prefix each output line with its line number"),
+            )
+            .arg(
+                Arg::with_name(byte_offset_argument)
+                    .short("b")
+                    .long("byte-offset")
+                    .takes_value(false)
+                    .help("Prefix each output line with the byte offset of the line (or matched token)"),
+            )
+            .arg(
+                Arg::with_name(only_matching_argument)
+                    .long("only-matching")
+                    .takes_value(false)
+                    .conflicts_with_all(&[invert_match_argument, mode_argument])
+                    .help("Print only matched tokens, each on a separate line prefixed with line number and token position (same as mode 'only-matching' with --line-number)"),
+            )
+            .arg(
+                Arg::with_name(delimiter_argument)
+                    .short("d")
+                    .long("delimiter")
+                    .value_name("delimiter")
+                    .help("Print matched tokens of each line joined by delimiter instead of on separate lines (mode 'only-matching' only)"),
+            )
+            .arg(
+                Arg::with_name(after_context_argument)
+                    .short("A")
+                    .long("after-context")
+                    .value_name("num")
+                    .validator(Arguments::validate_number)
+                    .help("Print num lines of trailing context after matching lines"),
+            )
+            .arg(
+                Arg::with_name(before_context_argument)
+                    .short("B")
+                    .long("before-context")
+                    .value_name("num")
+                    .validator(Arguments::validate_number)
+                    .help("Print num lines of leading context before matching lines"),
+            )
+            .arg(
+                Arg::with_name(context_argument)
+                    .short("C")
+                    .long("context")
+                    .value_name("num")
+                    .validator(Arguments::validate_number)
+                    .help("Print num lines of leading and trailing context around matching lines"),
+            )
+            .arg(
+                Arg::with_name(recursive_argument)
+                    .short("R")
+                    .long("recursive")
+                    .takes_value(false)
+                    .help("Read all files under input directories recursively"),
             )
             .arg(
                 Arg::with_name(input_argument)
                     .short("i")
                     .long("input-file")
                     .value_name("input-file")
-                    .help("Input file to read (stdin if not specified)")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Input file(s), directories, or glob patterns to read (stdin if not specified or '-')")
                     .display_order(1)
                     .next_line_help(true),
             )
@@ -128,15 +355,54 @@ impl Arguments {
                     .display_order(2)
                     .next_line_help(true),
             )
+            .arg(
+                Arg::with_name(output_format_argument)
+                    .long("output-format")
+                    .value_name("output-format")
+                    .possible_values(&[TEXT, JSON, NDJSON, TSV, CSV])
+                    .conflicts_with_all(&[invert_match_argument, only_matching_argument, mode_argument])
+                    .help("Output format [default: text]")
+                    .long_help("Output format to use; format 'text' prints lines according to the filter mode, formats 'json' and 'ndjson' print a JSON array or newline delimited JSON objects with line number, byte offset, text, and the matched tokens with position, byte range, and type of each matching line, formats 'tsv' and 'csv' print the values projected by --print of each matching line or the table of --aggregate\n")
+                    .display_order(3)
+                    .next_line_help(true),
+            )
+            .arg(
+                Arg::with_name(print_argument)
+                    .short("p")
+                    .long("print")
+                    .value_name("projection")
+                    .conflicts_with_all(&[invert_match_argument, only_matching_argument, mode_argument])
+                    .help("Print comma separated variables and functions of matching lines as TSV (or CSV) instead of lines")
+                    .long_help("Print the values of comma separated <variable>s and <function>s such as '$dateTime, port($ipSocketAddress)' parsed from each matching line as TSV (or CSV with --output-format csv) instead of matching lines; the first value of each <variable> is printed unless --print-all is specified\n")
+                    .display_order(3)
+                    .next_line_help(true),
+            )
+            .arg(
+                Arg::with_name(print_all_argument)
+                    .long("print-all")
+                    .takes_value(false)
+                    .requires(print_argument)
+                    .help("Print all values of each variable of --print separated by space instead of only the first value"),
+            )
+            .arg(
+                Arg::with_name(aggregate_argument)
+                    .long("aggregate")
+                    .value_name("aggregation")
+                    .conflicts_with_all(&[invert_match_argument, only_matching_argument, mode_argument, print_argument])
+                    .help("Aggregate values of matching lines and print the results as TSV (or CSV) table at the end")
+                    .long_help("Aggregate values of matching lines using comma separated aggregates count(), sum(<number>), min(<number>), max(<number>), and avg(<number>) optionally grouped by comma separated <variable>s and <function>s such as 'count(), sum($integer@5) by $id@1' and print the results as TSV (or CSV with --output-format csv) table at the end; a <number> is an $integer, $float, or port(<variable>) and only the first value on each line is aggregated\n")
+                    .display_order(3)
+                    .next_line_help(true),
+            )
             .arg(
                 Arg::with_name(mode_argument)
                     .short("m")
                     .long("mode")
                     .value_name("mode")
                     .default_value(FILTER_HIGHLIGHT)
-                    .possible_values(&[FILTER, HIGHLIGHT, FILTER_HIGHLIGHT])
+                    .possible_values(&[FILTER, HIGHLIGHT, FILTER_HIGHLIGHT, REJECT, ONLY_MATCHING])
                     .help("Filter mode")
-                    .long_help("Filter mode to use; in mode 'filter' only matching lines are printed, in mode 'filter-and-highlight' only matching lins are printed and matching tokens are highlighted in color, in mode 'highlight' all lines are printed and matching tokens are highlighted in color\n")
+                    .long_help("Filter mode to use; in mode 'filter' only matching lines are printed, in mode 'filter-and-highlight' only matching lins are printed and matching tokens are highlighted in color, in mode 'highlight' all lines are printed and matching tokens are highlighted in color, in mode 'reject' only lines which do not match are printed, in mode 'only-matching' only matching tokens are printed\n")
                     .display_order(3)
                     .next_line_help(true),
             )
@@ -169,8 +435,10 @@ impl Arguments {
                     .long("date-format")
                     .value_name("date-format")
                     .default_value(DATE_FORMAT)
+                    .multiple(true)
+                    .number_of_values(1)
                     .validator(Arguments::validate_strftime)
-                    .help("$date format using chrono::format::strftime specifiers (must not include separators)")
+                    .help("$date format using chrono::format::strftime specifiers (must not start or end with separators, repeat to try multiple formats in order)")
                     .display_order(6)
                     .next_line_help(true),
             )
@@ -179,8 +447,10 @@ impl Arguments {
                     .long("time-format")
                     .value_name("time-format")
                     .default_value(TIME_FORMAT)
+                    .multiple(true)
+                    .number_of_values(1)
                     .validator(Arguments::validate_strftime)
-                    .help("$time format using chrono::format::strftime specifiers (must not include separators)")
+                    .help("$time format using chrono::format::strftime specifiers (must not start or end with separators, repeat to try multiple formats in order)")
                     .display_order(7)
                     .next_line_help(true),
             )
@@ -189,8 +459,10 @@ impl Arguments {
                     .long("date-time-format")
                     .value_name("date-time-format")
                     .default_value(DATE_TIME_FORMAT)
+                    .multiple(true)
+                    .number_of_values(1)
                     .validator(Arguments::validate_strftime)
-                    .help("$dateTime format using chrono::format::strftime specifiers (must not include separators)")
+                    .help("$dateTime format using chrono::format::strftime specifiers (must not start or end with separators, repeat to try multiple formats in order)")
                     .display_order(8)
                     .next_line_help(true),
             )
@@ -199,11 +471,22 @@ impl Arguments {
                     .long("local-date-time-format")
                     .value_name("local-date-time-format")
                     .default_value(LOCAL_DATE_TIME_FORMAT)
+                    .multiple(true)
+                    .number_of_values(1)
                     .validator(Arguments::validate_strftime)
-                    .help("$localDateTime format using chrono::format::strftime specifiers (must not include separators)")
+                    .help("$localDateTime format using chrono::format::strftime specifiers (must not start or end with separators, repeat to try multiple formats in order)")
                     .display_order(9)
                     .next_line_help(true),
             )
+            .arg(
+                Arg::with_name(now_argument)
+                    .long("now")
+                    .value_name("dateTime")
+                    .validator(Arguments::validate_now)
+                    .help("RFC 3339 dateTime to resolve relative literals such as 'now-1h', 'today', or '-15m' against [default: current time]")
+                    .display_order(10)
+                    .next_line_help(true),
+            )
             .arg(
                 Arg::with_name(expression_argument)
                     .help("Filter expression applied to tokens found on each input line")
@@ -211,29 +494,81 @@ impl Arguments {
                     .index(1)
                     .next_line_help(true)
                     .long_help(EXPRESSION_HELP)
+            )
+            .arg(
+                Arg::with_name(input_files_argument)
+                    .help("Input file(s), directories, or glob patterns to read (same as --input-file)")
+                    .multiple(true)
+                    .index(2)
+                    .next_line_help(true)
             );
 
         let argument_matches = semfilter_command.get_matches();
-        let input: Box<dyn Read> = match argument_matches.value_of(input_argument) {
-            None => Box::new(stdin()),
-            Some(input_file) => {
-                Box::new(File::open(input_file).context(format!("Failed to open input-file '{}'", input_file))?)
-            }
-        };
+        let input_files = [input_argument, input_files_argument]
+            .iter()
+            .filter_map(|argument| argument_matches.values_of(argument))
+            .flatten()
+            .collect();
+        let inputs = Inputs::resolve(input_files, argument_matches.is_present(recursive_argument));
         let output: Box<dyn Write> = match argument_matches.value_of(output_argument) {
             None => Box::new(stdout()),
             Some(output_file) => {
                 Box::new(File::open(output_file).context(format!("Failed to open output-file '{}'", output_file))?)
             }
         };
-        let mode = Mode::from_str(argument_matches.value_of(mode_argument).unwrap())?;
+        let only_matching = argument_matches.is_present(only_matching_argument);
+        let mode = match (argument_matches.is_present(invert_match_argument), only_matching) {
+            (true, _) => Mode::Reject,
+            (_, true) => Mode::OnlyMatching,
+            _ => Mode::from_str(argument_matches.value_of(mode_argument).unwrap())?,
+        };
         let count = argument_matches.is_present(count_argument);
+        let line_number = argument_matches.is_present(line_number_argument) || only_matching;
+        let byte_offset = argument_matches.is_present(byte_offset_argument);
+        let delimiter = argument_matches.value_of(delimiter_argument).map(String::from);
+        let print = argument_matches.value_of(print_argument).map(String::from);
+        let print_all = argument_matches.is_present(print_all_argument);
+        let aggregate = argument_matches.value_of(aggregate_argument).map(String::from);
+        let follow = argument_matches.is_present(follow_argument);
+        let count_interval = Arguments::number(argument_matches.value_of(count_interval_argument))
+            .map(|seconds| Duration::from_secs(seconds as u64));
+        if follow && !matches!(inputs.as_slice(), [Ok(Input::File(_))]) {
+            return Err(anyhow!("Following requires a single input-file"));
+        }
+        let encoding = Encoding::for_label(argument_matches.value_of(encoding_argument).unwrap().as_bytes()).unwrap();
+        let binary_files = BinaryFiles::from_str(argument_matches.value_of(binary_files_argument).unwrap())?;
+        let record_separator = match argument_matches.value_of(record_separator_argument) {
+            Some(record_separator) => RecordSeparator::Pattern(Regex::new(record_separator).unwrap()),
+            None if argument_matches.is_present(null_data_argument) => RecordSeparator::Null,
+            None => RecordSeparator::Newline,
+        };
+        let tabular = print.is_some() || aggregate.is_some();
+        let format = match (argument_matches.value_of(output_format_argument), tabular) {
+            (None, true) => Format::Tsv,
+            (Some(format @ (JSON | NDJSON)), true) => {
+                return Err(anyhow!(
+                    "Output format '{}' can not be used with --print or --aggregate",
+                    format
+                ));
+            }
+            (Some(format @ (TSV | CSV)), false) => {
+                return Err(anyhow!("Output format '{}' requires --print or --aggregate", format));
+            }
+            (format, _) => Format::from_str(format.unwrap_or(TEXT))?,
+        };
+        let context = Arguments::number(argument_matches.value_of(context_argument)).unwrap_or(0);
+        let after_context = Arguments::number(argument_matches.value_of(after_context_argument)).unwrap_or(context);
+        let before_context = Arguments::number(argument_matches.value_of(before_context_argument)).unwrap_or(context);
         let expression = String::from(argument_matches.value_of(expression_argument).unwrap());
         let formats = Formats {
-            date: String::from(argument_matches.value_of(date_format_argument).unwrap()),
-            time: String::from(argument_matches.value_of(time_format_argument).unwrap()),
-            date_time: String::from(argument_matches.value_of(date_time_format_argument).unwrap()),
-            local_date_time: String::from(argument_matches.value_of(local_date_time_format_argument).unwrap()),
+            date: Arguments::formats(argument_matches.values_of(date_format_argument).unwrap()),
+            time: Arguments::formats(argument_matches.values_of(time_format_argument).unwrap()),
+            date_time: Arguments::formats(argument_matches.values_of(date_time_format_argument).unwrap()),
+            local_date_time: Arguments::formats(argument_matches.values_of(local_date_time_format_argument).unwrap()),
+            now: match argument_matches.value_of(now_argument) {
+                Some(now) => DateTime::parse_from_rfc3339(now).unwrap(),
+                None => Local::now().fixed_offset(),
+            },
         };
         let add_separators = match argument_matches.values_of(add_separator_argument) {
             None => vec![],
@@ -249,7 +584,7 @@ impl Arguments {
         Validator::validate_separators(&expression, &separators, &formats).context("Invalid separators")?;
 
         Ok(Arguments {
-            input: input,
+            inputs: inputs,
             output: output,
             expression: expression,
             separators: separators,
@@ -257,6 +592,21 @@ impl Arguments {
                 formats: formats,
                 mode: mode,
                 count: count,
+                before_context: before_context,
+                after_context: after_context,
+                line_number: line_number,
+                byte_offset: byte_offset,
+                delimiter: delimiter,
+                format: format,
+                print: print,
+                print_all: print_all,
+                aggregate: aggregate,
+                follow: follow,
+                count_interval: count_interval,
+                decompress: !argument_matches.is_present(no_decompress_argument),
+                encoding: encoding,
+                binary_files: binary_files,
+                record_separator: record_separator,
             },
         })
     }
@@ -268,6 +618,45 @@ impl Arguments {
         return separators.into_iter().collect();
     }
 
+    fn formats(values: Values) -> Vec<String> {
+        values.map(String::from).collect()
+    }
+
+    fn number(value: Option<&str>) -> Option<usize> {
+        value.map(|value| usize::from_str(value).unwrap())
+    }
+
+    fn validate_number(number: String) -> Result<(), String> {
+        match usize::from_str(&number) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(format!("Number '{}' invalid", number)),
+        }
+    }
+
+    fn validate_encoding(encoding: String) -> Result<(), String> {
+        match Encoding::for_label(encoding.as_bytes()) {
+            Some(_) => Ok(()),
+            None => Err(format!("Encoding '{}' invalid", encoding)),
+        }
+    }
+
+    fn validate_record_separator(record_separator: String) -> Result<(), String> {
+        match Regex::new(&record_separator) {
+            Ok(regex) if regex.is_match(b"") => {
+                Err(format!("Record separator '{}' matches empty text", record_separator))
+            }
+            Ok(_) => Ok(()),
+            Err(error) => Err(format!("Record separator '{}' invalid: {}", record_separator, error)),
+        }
+    }
+
+    fn validate_now(now: String) -> Result<(), String> {
+        match DateTime::parse_from_rfc3339(&now) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(format!("DateTime '{}' invalid", now)),
+        }
+    }
+
     fn validate_strftime(format: String) -> Result<(), String> {
         match StrftimeItems::new(&format).position(|i| i == Item::Error) {
             None => Ok(()),
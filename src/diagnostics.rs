@@ -0,0 +1,587 @@
+use anyhow::{anyhow, Error};
+use peg::error::ParseError;
+use peg::str::LineCol;
+use std::ops::Range;
+
+pub const VARIABLES: &[&str] = &[
+    "$integer",
+    "$float",
+    "$id",
+    "$text",
+    "$word",
+    "$date",
+    "$time",
+    "$dateTime",
+    "$localDateTime",
+    "$timestamp",
+    "$ipAddress",
+    "$ipv4Address",
+    "$ipv6Address",
+    "$ipSocketAddress",
+    "$ipv4SocketAddress",
+    "$ipv6SocketAddress",
+    "$ipNetwork",
+    "$ipv4Network",
+    "$ipv6Network",
+    "$semanticVersion",
+];
+pub const FUNCTIONS: &[&str] = &["port", "ip"];
+pub const QUANTIFIERS: &[&str] = &["all", "none", "count"];
+
+const KEYWORDS: &[&str] = &["and", "or", "not", "followed-by", "within", "same-token"];
+const COMPARATORS: &[&str] = &[
+    "==",
+    "!=",
+    ">",
+    ">=",
+    "<",
+    "<=",
+    "=~",
+    "!~",
+    "contains",
+    "starts-with",
+    "ends-with",
+    "matches-regex",
+    "matches",
+    "in",
+    "not in",
+];
+const BASIC_COMPARATORS: &[&str] = &["==", "!=", ">", ">=", "<", "<="];
+const TEXT_COMPARATORS: &[&str] = &["contains", "starts-with", "ends-with", "=~", "!~", "matches-regex"];
+const NETWORK_COMPARATORS: &[&str] = &["in", "not in"];
+const VERSION_COMPARATORS: &[&str] = &["matches"];
+const SOCKET_ADDRESSES: &[&str] = &["$ipSocketAddress", "$ipv4SocketAddress", "$ipv6SocketAddress"];
+
+struct Lexeme<'a> {
+    start: usize,
+    text: &'a str,
+}
+
+impl<'a> Lexeme<'a> {
+    fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    fn is_variable(&self) -> bool {
+        self.text.starts_with('$')
+    }
+
+    // strips the selector from a variable, e.g. `$integer@2` or `$integer[2]` become `$integer`
+    fn name(&self) -> &'a str {
+        match self.text.find(['@', '[']) {
+            Some(index) => &self.text[..index],
+            None => self.text,
+        }
+    }
+}
+
+pub struct Diagnostics {}
+
+impl Diagnostics {
+    pub fn diagnose(expression: &str, error: &ParseError<LineCol>) -> Error {
+        let lexemes = Diagnostics::lexemes(expression);
+        let offset = error.location.offset;
+
+        let (span, message) = Diagnostics::unknown_name(&lexemes, offset)
+            .or_else(|| Diagnostics::unsupported_comparator(&lexemes, offset))
+            .or_else(|| Diagnostics::invalid_value(expression, &lexemes, offset))
+            .unwrap_or_else(|| Diagnostics::unexpected(expression, &lexemes, offset, error));
+
+        anyhow!("{}\n{}", message, Diagnostics::caret(expression, span))
+    }
+
+    fn lexemes(expression: &str) -> Vec<Lexeme<'_>> {
+        let mut lexemes = Vec::new();
+        let mut characters = expression.char_indices().peekable();
+        while let Some((start, character)) = characters.next() {
+            let mut end = start + character.len_utf8();
+            if character.is_whitespace() {
+                continue;
+            } else if character == '"' || character == '\'' {
+                while let Some((index, next)) = characters.next() {
+                    end = index + next.len_utf8();
+                    if next == '\\' {
+                        if let Some((index, escaped)) = characters.next() {
+                            end = index + escaped.len_utf8();
+                        }
+                    } else if next == character {
+                        break;
+                    }
+                }
+            } else if character == '$' {
+                let mut selector = false;
+                while let Some(&(index, next)) = characters.peek() {
+                    match next {
+                        '@' | '[' if !selector => selector = true,
+                        ']' if selector => {
+                            characters.next();
+                            end = index + 1;
+                            break;
+                        }
+                        _ if next.is_ascii_alphanumeric() => {}
+                        _ => break,
+                    }
+                    characters.next();
+                    end = index + 1;
+                }
+            } else if Diagnostics::is_symbol(character) {
+                while let Some(&(index, next)) = characters.peek() {
+                    if !Diagnostics::is_symbol(next) {
+                        break;
+                    }
+                    characters.next();
+                    end = index + 1;
+                }
+            } else if character != '(' && character != ')' {
+                while let Some(&(index, next)) = characters.peek() {
+                    if next.is_whitespace() || next == '(' || next == ')' || Diagnostics::is_symbol(next) {
+                        break;
+                    }
+                    characters.next();
+                    end = index + next.len_utf8();
+                }
+            }
+
+            lexemes.push(Lexeme {
+                start,
+                text: &expression[start..end],
+            });
+        }
+
+        lexemes
+    }
+
+    fn is_symbol(character: char) -> bool {
+        character == '=' || character == '!' || character == '<' || character == '>' || character == '~'
+    }
+
+    fn unknown_name(lexemes: &[Lexeme], offset: usize) -> Option<(Range<usize>, String)> {
+        for (index, lexeme) in lexemes
+            .iter()
+            .enumerate()
+            .take_while(|(_, lexeme)| lexeme.start <= offset)
+        {
+            let name = lexeme.name();
+            let followed_by_parenthesis = lexemes.get(index + 1).is_some_and(|next| next.text == "(");
+            if lexeme.is_variable() {
+                let field = name.len() > 1 && name[1..].chars().all(|c| c.is_ascii_digit());
+                if !field && !VARIABLES.contains(&name) {
+                    let message = format!("unknown variable `{}`{}", name, Diagnostics::suggest(name, VARIABLES));
+                    return Some((lexeme.start..lexeme.start + name.len(), message));
+                }
+            } else if followed_by_parenthesis && name.starts_with(char::is_alphabetic) && !KEYWORDS.contains(&name) {
+                if QUANTIFIERS.contains(&name) {
+                    continue;
+                }
+
+                if !FUNCTIONS.contains(&name) {
+                    let candidates = [FUNCTIONS, QUANTIFIERS].concat();
+                    let message = format!("unknown function `{}`{}", name, Diagnostics::suggest(name, &candidates));
+                    return Some((lexeme.start..lexeme.end(), message));
+                }
+
+                if let Some(argument) = lexemes.get(index + 2).filter(|argument| argument.is_variable()) {
+                    if VARIABLES.contains(&argument.name()) && !SOCKET_ADDRESSES.contains(&argument.name()) {
+                        let message = format!(
+                            "function `{}` is not supported for `{}`, expected one of `{}`",
+                            name,
+                            argument.name(),
+                            SOCKET_ADDRESSES.join("`, `")
+                        );
+                        return Some((argument.start..argument.start + argument.name().len(), message));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn unsupported_comparator(lexemes: &[Lexeme], offset: usize) -> Option<(Range<usize>, String)> {
+        let index = Diagnostics::position(lexemes, offset)?;
+        let (comparator, span) = match (lexemes[index].text, lexemes.get(index + 1)) {
+            ("not", Some(next)) if next.text == "in" => ("not in", lexemes[index].start..next.end()),
+            (comparator, _) => (comparator, lexemes[index].start..lexemes[index].end()),
+        };
+        if !COMPARATORS.contains(&comparator) {
+            return None;
+        }
+
+        let variable = Diagnostics::variable(lexemes, index)?;
+        if Diagnostics::comparators(&variable).contains(&comparator) {
+            return None;
+        }
+
+        Some((
+            span,
+            format!("comparator `{}` is not supported for `{}`", comparator, variable),
+        ))
+    }
+
+    fn invalid_value(expression: &str, lexemes: &[Lexeme], offset: usize) -> Option<(Range<usize>, String)> {
+        let index = Diagnostics::position(lexemes, offset).unwrap_or(lexemes.len());
+        let value = Diagnostics::value_at(expression, lexemes, index);
+
+        // values failing validation are reported at their end rather than at their start
+        match lexemes.iter().position(|lexeme| lexeme.end() == offset) {
+            Some(index) if value.is_none() => Diagnostics::value_at(expression, lexemes, index),
+            _ => value,
+        }
+    }
+
+    fn value_at(expression: &str, lexemes: &[Lexeme], index: usize) -> Option<(Range<usize>, String)> {
+        let (comparator, variable_index) = match index {
+            0 => return None,
+            1 => (lexemes[0].text, 0),
+            _ if lexemes[index - 2].text == "not" && lexemes[index - 1].text == "in" => ("not in", index - 2),
+            _ => (lexemes[index - 1].text, index - 1),
+        };
+        if !COMPARATORS.contains(&comparator) {
+            return None;
+        }
+
+        let variable = Diagnostics::variable(lexemes, variable_index)?;
+        let value = Diagnostics::value(&variable, comparator);
+        match lexemes.get(index) {
+            Some(lexeme) => Some((
+                lexeme.start..lexeme.end(),
+                format!("invalid value `{}` for `{}`, expected {}", lexeme.text, variable, value),
+            )),
+            None => Some((
+                expression.len()..expression.len(),
+                format!("missing value for `{}`, expected {}", variable, value),
+            )),
+        }
+    }
+
+    fn unexpected(
+        expression: &str,
+        lexemes: &[Lexeme],
+        offset: usize,
+        error: &ParseError<LineCol>,
+    ) -> (Range<usize>, String) {
+        let mut expected: Vec<String> = error
+            .expected
+            .tokens()
+            .filter(|token| token.starts_with('"') && token.len() > 2)
+            .map(|token| &token[1..token.len() - 1])
+            .filter(|token| !VARIABLES.contains(token) && !FUNCTIONS.contains(token) && !QUANTIFIERS.contains(token))
+            .map(|token| format!("`{}`", token))
+            .collect();
+        expected.sort();
+        expected.dedup();
+        let expected = match expected.len() {
+            0 => String::new(),
+            1 => format!(", expected {}", expected[0]),
+            _ => format!(", expected one of {}", expected.join(", ")),
+        };
+
+        match Diagnostics::position(lexemes, offset) {
+            Some(index) => (
+                lexemes[index].start..lexemes[index].end(),
+                format!("unexpected `{}`{}", lexemes[index].text, expected),
+            ),
+            None => (
+                expression.len()..expression.len(),
+                format!("unexpected end of expression{}", expected),
+            ),
+        }
+    }
+
+    // index of the lexeme at or after the offset
+    fn position(lexemes: &[Lexeme], offset: usize) -> Option<usize> {
+        lexemes.iter().position(|lexeme| offset < lexeme.end())
+    }
+
+    // variable or function expression ending right before the lexeme at index
+    fn variable(lexemes: &[Lexeme], index: usize) -> Option<String> {
+        let previous = lexemes.get(index.checked_sub(1)?)?;
+        if previous.is_variable() {
+            return Some(String::from(previous.name()));
+        }
+
+        if previous.text == ")" && index >= 4 && lexemes[index - 3].text == "(" {
+            let function = lexemes[index - 4].text;
+            let argument = &lexemes[index - 2];
+            if FUNCTIONS.contains(&function) && argument.is_variable() {
+                return Some(format!("{}({})", function, argument.name()));
+            }
+        }
+
+        None
+    }
+
+    fn comparators(variable: &str) -> Vec<&'static str> {
+        let mut comparators = BASIC_COMPARATORS.to_vec();
+        match variable {
+            _ if Diagnostics::is_id(variable) => comparators.extend(TEXT_COMPARATORS),
+            "$ipAddress" | "$ipv4Address" | "$ipv6Address" => comparators.extend(NETWORK_COMPARATORS),
+            _ if variable.starts_with("ip(") => comparators.extend(NETWORK_COMPARATORS),
+            "$semanticVersion" => comparators.extend(VERSION_COMPARATORS),
+            _ => {}
+        }
+
+        comparators
+    }
+
+    // `$text`, `$word` and fields `$<n>` are parsed by the same rule as `$id`
+    fn is_id(variable: &str) -> bool {
+        matches!(variable, "$id" | "$text" | "$word") || variable[1..].chars().all(|c| c.is_ascii_digit())
+    }
+
+    fn value(variable: &str, comparator: &str) -> String {
+        let class = match variable {
+            "port($ipSocketAddress)" | "port($ipv4SocketAddress)" | "port($ipv6SocketAddress)" => "port",
+            "ip($ipSocketAddress)" => "ipAddress",
+            "ip($ipv4SocketAddress)" => "ipv4Address",
+            "ip($ipv6SocketAddress)" => "ipv6Address",
+            _ => &variable[1..],
+        };
+
+        match (class, comparator) {
+            (_, "=~") | (_, "!~") | (_, "matches-regex") => String::from("<regex>"),
+            (_, "matches") => String::from("<semanticVersionRequirement>"),
+            (_, "in") | (_, "not in") => format!("<{}>", class.replace("Address", "Network")),
+            _ if class.chars().all(|c| c.is_ascii_digit()) => String::from("<word> or <string>"),
+            _ if Diagnostics::is_id(variable) => String::from("<id> or <string>"),
+            _ => format!("<{}>", class),
+        }
+    }
+
+    fn suggest(name: &str, candidates: &[&str]) -> String {
+        let lowercase_name = name.to_lowercase();
+        let nearest = candidates
+            .iter()
+            .map(|candidate| {
+                (
+                    strsim::levenshtein(&lowercase_name, &candidate.to_lowercase()),
+                    candidate,
+                )
+            })
+            .min();
+
+        match nearest {
+            Some((distance, candidate)) if distance <= 2.max(name.len() / 3) => {
+                format!(", did you mean `{}`?", candidate)
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn caret(expression: &str, span: Range<usize>) -> String {
+        let line_start = expression[..span.start].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = expression[span.start..]
+            .find('\n')
+            .map_or(expression.len(), |index| span.start + index);
+        let indent = expression[line_start..span.start].chars().count();
+        let width = expression[span.start..span.end.min(line_end)].chars().count().max(1);
+
+        format!(
+            "  {}\n  {}{}",
+            &expression[line_start..line_end],
+            " ".repeat(indent),
+            "^".repeat(width)
+        )
+    }
+}
+
+#[cfg(test)]
+mod diagnostics_tests {
+    use super::*;
+    use crate::expression::expression;
+    use crate::filter::test_utils;
+    use std::collections::HashSet;
+
+    #[test]
+    fn unsupported_comparator() {
+        assert_diagnostic(
+            "$integer contains 5",
+            "comparator `contains` is not supported for `$integer`",
+            "  $integer contains 5\n           ^^^^^^^^",
+        );
+        assert_diagnostic(
+            "$id == a and $date@1 in 10.0.0.0/8",
+            "comparator `in` is not supported for `$date`",
+            "  $id == a and $date@1 in 10.0.0.0/8\n                       ^^",
+        );
+        assert_diagnostic(
+            "port($ipv4SocketAddress) not in 10.0.0.0/8",
+            "comparator `not in` is not supported for `port($ipv4SocketAddress)`",
+            "  port($ipv4SocketAddress) not in 10.0.0.0/8\n                           ^^^^^^",
+        );
+    }
+
+    #[test]
+    fn unknown_variable() {
+        assert_diagnostic(
+            "$ipv4address == 1.2.3.4",
+            "unknown variable `$ipv4address`, did you mean `$ipv4Address`?",
+            "  $ipv4address == 1.2.3.4\n  ^^^^^^^^^^^^",
+        );
+        assert_diagnostic(
+            "$integer > 5 or $flaot < 1.0",
+            "unknown variable `$flaot`, did you mean `$float`?",
+            "  $integer > 5 or $flaot < 1.0\n                  ^^^^^^",
+        );
+        assert_diagnostic(
+            "$qpanda == 1",
+            "unknown variable `$qpanda`",
+            "  $qpanda == 1\n  ^^^^^^^",
+        );
+    }
+
+    #[test]
+    fn unknown_function() {
+        assert_diagnostic(
+            "prot($ipv4SocketAddress) == 22",
+            "unknown function `prot`, did you mean `port`?",
+            "  prot($ipv4SocketAddress) == 22\n  ^^^^",
+        );
+        assert_diagnostic(
+            "port($ipv4Address) == 22",
+            "function `port` is not supported for `$ipv4Address`, expected one of `$ipSocketAddress`, `$ipv4SocketAddress`, `$ipv6SocketAddress`",
+            "  port($ipv4Address) == 22\n       ^^^^^^^^^^^^",
+        );
+    }
+
+    #[test]
+    fn quantifiers() {
+        assert_diagnostic(
+            "cuont($integer > 1) > 2",
+            "unknown function `cuont`, did you mean `count`?",
+            "  cuont($integer > 1) > 2\n  ^^^^^",
+        );
+        assert_diagnostic(
+            "all($integer contains 1)",
+            "comparator `contains` is not supported for `$integer`",
+            "  all($integer contains 1)\n               ^^^^^^^^",
+        );
+    }
+
+    #[test]
+    fn invalid_value() {
+        assert_diagnostic(
+            "$integer == a",
+            "invalid value `a` for `$integer`, expected <integer>",
+            "  $integer == a\n              ^",
+        );
+        assert_diagnostic(
+            "$date > 2021-13-01",
+            "invalid value `2021-13-01` for `$date`, expected <date>",
+            "  $date > 2021-13-01\n          ^^^^^^^^^^",
+        );
+        assert_diagnostic(
+            "$ipAddress not in 10.0.0.0/33",
+            "invalid value `10.0.0.0/33` for `$ipAddress`, expected <ipNetwork>",
+            "  $ipAddress not in 10.0.0.0/33\n                    ^^^^^^^^^^^",
+        );
+        assert_diagnostic(
+            "$float <",
+            "missing value for `$float`, expected <float>",
+            "  $float <\n          ^",
+        );
+        assert_diagnostic(
+            "$3 == %",
+            "invalid value `%` for `$3`, expected <word> or <string>",
+            "  $3 == %\n        ^",
+        );
+    }
+
+    #[test]
+    fn keywords_before_parenthesis() {
+        assert_diagnostic(
+            "$id == a followed-by ($integer > 1) within 2 ($integer contains 1)",
+            "comparator `contains` is not supported for `$integer`",
+            "  $id == a followed-by ($integer > 1) within 2 ($integer contains 1)\n                                                         ^^^^^^^^",
+        );
+    }
+
+    #[test]
+    fn unexpected() {
+        assert_diagnostic(
+            "$integer > 9 && $integer > 8",
+            "unexpected `&&`, expected one of `and`, `followed-by`, `or`, `same-token`, `within`",
+            "  $integer > 9 && $integer > 8\n               ^^",
+        );
+        assert_diagnostic(
+            "$integer > 9 and",
+            "unexpected end of expression, expected one of `!`, `$`, `(`, `not`",
+            "  $integer > 9 and\n                  ^",
+        );
+    }
+
+    #[test]
+    fn multiline() {
+        assert_diagnostic(
+            "$integer > 9\n and $id contains a9\n or $integer contains 5",
+            "comparator `contains` is not supported for `$integer`",
+            "   or $integer contains 5\n               ^^^^^^^^",
+        );
+    }
+
+    #[test]
+    fn names_of_grammar() {
+        // setup
+        let formats = test_utils::default_formats();
+        let names: HashSet<&str> = [VARIABLES, FUNCTIONS, QUANTIFIERS, &["not"]]
+            .concat()
+            .into_iter()
+            .collect();
+
+        // exercise
+        let error = expression::compile("?", &formats).err().unwrap();
+        let expected: HashSet<&str> = error
+            .expected
+            .tokens()
+            .filter(|token| token.starts_with('"') && token.len() > 3)
+            .map(|token| &token[1..token.len() - 1])
+            .filter(|token| token.starts_with(|c: char| c == '$' || c.is_ascii_lowercase()))
+            .collect();
+
+        // verify
+        assert_eq!(expected, names);
+    }
+
+    #[test]
+    fn comparators_of_grammar() {
+        // setup
+        let formats = test_utils::default_formats();
+        let mut variables: Vec<String> = VARIABLES.iter().map(|variable| String::from(*variable)).collect();
+        variables.push(String::from("$1"));
+        for function in FUNCTIONS {
+            variables.extend(
+                SOCKET_ADDRESSES
+                    .iter()
+                    .map(|variable| format!("{}({})", function, variable)),
+            );
+        }
+
+        for variable in variables {
+            // exercise
+            let error = expression::compile(&format!("{} ?", variable), &formats).err().unwrap();
+            let expected: HashSet<&str> = error
+                .expected
+                .tokens()
+                .filter(|token| token.starts_with('"') && token.len() > 2)
+                .map(|token| match &token[1..token.len() - 1] {
+                    "not" => "not in",
+                    token => token,
+                })
+                .collect();
+
+            // verify
+            let comparators: HashSet<&str> = Diagnostics::comparators(&variable).into_iter().collect();
+            assert_eq!(expected, comparators, "{}", variable);
+        }
+    }
+
+    fn assert_diagnostic(expression: &str, message: &str, caret: &str) {
+        let error = expression::compile(expression, &test_utils::default_formats())
+            .err()
+            .unwrap();
+        assert_eq!(
+            Diagnostics::diagnose(expression, &error).to_string(),
+            format!("{}\n{}", message, caret)
+        );
+    }
+}
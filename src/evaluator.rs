@@ -0,0 +1,387 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::Term;
+use crate::tokenizer::Position;
+use crate::tokenizer::Token;
+
+pub type Terms<T> = Box<dyn Fn(&[Token]) -> Vec<Term<T>>>;
+pub type Predicate<T> = Box<dyn Fn(&T) -> bool>;
+pub type Count = Box<dyn Fn(usize) -> bool>;
+pub type Types = HashMap<Position, &'static str>;
+type Fields = (usize, usize);
+type Value = (Vec<Position>, Fields);
+
+pub struct Variable<T> {
+    pub name: &'static str,
+    terms: Terms<T>,
+}
+
+impl<T: 'static> Variable<T> {
+    pub fn new(name: &'static str, terms: Terms<T>) -> Self {
+        Variable { name, terms }
+    }
+
+    pub fn terms(&self, tokens: &[Token]) -> Vec<Term<T>> {
+        (self.terms)(tokens)
+    }
+
+    // applies a function to the value of each term, the variable keeps the name of the type it was parsed as
+    pub fn map<U: 'static, F>(self, function: F) -> Variable<U>
+    where
+        F: Fn(T) -> U + 'static,
+    {
+        let terms = self.terms;
+        Variable::new(
+            self.name,
+            Box::new(move |tokens: &[Token]| {
+                terms(tokens)
+                    .into_iter()
+                    .map(|term| Term {
+                        position: term.position,
+                        span: term.span,
+                        value: function(term.value),
+                    })
+                    .collect()
+            }),
+        )
+    }
+}
+
+pub enum Selector {
+    Field(usize),
+    Occurrence(usize),
+}
+
+pub fn select<T: 'static>(variable: Variable<T>, selector: Option<Selector>) -> Variable<T> {
+    let name = variable.name;
+    match selector {
+        None => variable,
+        Some(Selector::Field(field)) => Variable::new(
+            name,
+            // values spanning multiple tokens are selected by their first field
+            Box::new(move |tokens: &[Token]| {
+                let fields = fields(tokens);
+                variable
+                    .terms(tokens)
+                    .into_iter()
+                    .filter(|term| fields.get(&term.position) == Some(&(field - 1)))
+                    .collect()
+            }),
+        ),
+        Some(Selector::Occurrence(occurrence)) => Variable::new(
+            name,
+            Box::new(move |tokens: &[Token]| {
+                variable
+                    .terms(tokens)
+                    .into_iter()
+                    .nth(occurrence - 1)
+                    .into_iter()
+                    .collect()
+            }),
+        ),
+    }
+}
+
+pub trait Evaluate {
+    fn evaluate(&self, tokens: &[Token]) -> Option<HashSet<Position>> {
+        self.evaluate_types(tokens, None)
+    }
+
+    // additionally collects the name of the variable type each matched token was parsed as, if types are given
+    fn evaluate_types(&self, tokens: &[Token], types: Option<&mut Types>) -> Option<HashSet<Position>>;
+}
+
+pub trait Quantify: Evaluate {
+    fn quantify(self: Box<Self>, quantifier: Quantifier) -> Box<dyn Quantify>;
+}
+
+pub enum Quantifier {
+    Any,
+    All,
+    None,
+    Count(Count),
+}
+
+pub struct Condition<T> {
+    variable: Variable<T>,
+    predicate: Predicate<T>,
+    quantifier: Quantifier,
+}
+
+impl<T> Condition<T> {
+    pub fn new(variable: Variable<T>, predicate: Predicate<T>) -> Self {
+        Condition {
+            variable,
+            predicate,
+            quantifier: Quantifier::Any,
+        }
+    }
+}
+
+impl<T: 'static> Evaluate for Condition<T> {
+    fn evaluate_types(&self, tokens: &[Token], types: Option<&mut Types>) -> Option<HashSet<Position>> {
+        let terms = self.variable.terms(tokens);
+        let positions = matches(&terms, |term| (self.predicate)(&term.value));
+        let result = match &self.quantifier {
+            Quantifier::Any if !positions.is_empty() => Some(positions),
+            Quantifier::All if !terms.is_empty() && terms.iter().all(|term| (self.predicate)(&term.value)) => {
+                Some(positions)
+            }
+            Quantifier::None if positions.is_empty() => Some(positions),
+            // values spanning multiple tokens are counted once
+            Quantifier::Count(count) if count(terms.iter().filter(|term| (self.predicate)(&term.value)).count()) => {
+                Some(positions)
+            }
+            _ => None,
+        };
+
+        if let (Some(positions), Some(types)) = (&result, types) {
+            for position in positions {
+                types.entry(*position).or_insert(self.variable.name);
+            }
+        }
+
+        result
+    }
+}
+
+impl<T: 'static> Quantify for Condition<T> {
+    fn quantify(mut self: Box<Self>, quantifier: Quantifier) -> Box<dyn Quantify> {
+        self.quantifier = quantifier;
+        self
+    }
+}
+
+pub enum Correlation {
+    FollowedBy,
+    Within(usize),
+    SameToken,
+}
+
+impl Correlation {
+    // correlates the first and last field of values, which differ for values spanning multiple tokens
+    fn correlates(&self, left: &Fields, right: &Fields) -> bool {
+        match self {
+            Self::FollowedBy => left.1 + 1 == right.0,
+            Self::Within(distance) => left.0.max(right.0) <= left.1.min(right.1) + distance,
+            Self::SameToken => left.0 <= right.1 && right.0 <= left.1,
+        }
+    }
+}
+
+pub enum Expression {
+    Or(Vec<Expression>),
+    And(Vec<Expression>),
+    Not(Box<Expression>),
+    Correlation(Box<Expression>, Vec<(Correlation, Expression)>),
+    Condition(Box<dyn Quantify>),
+}
+
+impl Expression {
+    pub fn or(mut expressions: Vec<Expression>) -> Self {
+        match expressions.len() {
+            1 => expressions.remove(0),
+            _ => Expression::Or(expressions),
+        }
+    }
+
+    pub fn and(mut expressions: Vec<Expression>) -> Self {
+        match expressions.len() {
+            1 => expressions.remove(0),
+            _ => Expression::And(expressions),
+        }
+    }
+
+    pub fn not(expression: Expression) -> Self {
+        match expression {
+            Expression::Not(expression) => *expression,
+            _ => Expression::Not(Box::new(expression)),
+        }
+    }
+
+    pub fn correlation(first: Expression, rest: Vec<(Correlation, Expression)>) -> Self {
+        match rest.is_empty() {
+            true => first,
+            false => Expression::Correlation(Box::new(first), rest),
+        }
+    }
+
+    pub fn condition<T: 'static, P>(variable: Variable<T>, predicate: P) -> Self
+    where
+        P: Fn(&T) -> bool + 'static,
+    {
+        Expression::Condition(Box::new(Condition::new(variable, Box::new(predicate))))
+    }
+
+    pub fn quantify(self, quantifier: Quantifier) -> Result<Self, &'static str> {
+        match self {
+            Expression::Condition(condition) => Ok(Expression::Condition(condition.quantify(quantifier))),
+            _ => Err("quantifiable condition"),
+        }
+    }
+}
+
+impl Evaluate for Expression {
+    fn evaluate_types(&self, tokens: &[Token], mut types: Option<&mut Types>) -> Option<HashSet<Position>> {
+        match self {
+            Self::Or(expressions) => {
+                let mut result: Option<HashSet<Position>> = None;
+                for expression in expressions {
+                    if let Some(positions) = expression.evaluate_types(tokens, types.as_deref_mut()) {
+                        result.get_or_insert_with(HashSet::new).extend(positions);
+                    }
+                }
+
+                result
+            }
+            Self::And(expressions) => {
+                // types are kept only if all conditions match
+                let mut branch = Types::new();
+                let mut result = HashSet::new();
+                for expression in expressions {
+                    result.extend(expression.evaluate_types(tokens, types.is_some().then_some(&mut branch))?);
+                }
+
+                merge(types, branch, &result);
+                Some(result)
+            }
+            Self::Not(expression) => match expression.evaluate(tokens) {
+                Some(_) => None,
+                None => Some(HashSet::new()),
+            },
+            Self::Correlation(first, rest) => {
+                let fields = fields(tokens);
+                let correlates =
+                    |correlation: &Correlation, left: &Value, right: &Value| correlation.correlates(&left.1, &right.1);
+
+                // forward pass: values reachable by a chain starting at the first expression
+                let mut branch = Types::new();
+                let matches = first.evaluate_types(tokens, types.is_some().then_some(&mut branch))?;
+                let mut chain = vec![values(matches, &fields)];
+                for (correlation, expression) in rest {
+                    let previous = chain.last().unwrap();
+                    let matches = expression.evaluate_types(tokens, types.is_some().then_some(&mut branch))?;
+                    let reachable: Vec<Value> = values(matches, &fields)
+                        .into_iter()
+                        .filter(|right| previous.iter().any(|left| correlates(correlation, left, right)))
+                        .collect();
+                    if reachable.is_empty() {
+                        return None;
+                    }
+                    chain.push(reachable);
+                }
+
+                // backward pass: keep only values which are part of a complete chain
+                for (index, (correlation, _)) in rest.iter().enumerate().rev() {
+                    let (previous, next) = chain.split_at_mut(index + 1);
+                    let next = &next[0];
+                    previous[index].retain(|left| next.iter().any(|right| correlates(correlation, left, right)));
+                }
+
+                let result = chain.into_iter().flatten().flat_map(|value| value.0).collect();
+                merge(types, branch, &result);
+                Some(result)
+            }
+            Self::Condition(condition) => condition.evaluate_types(tokens, types),
+        }
+    }
+}
+
+// keeps the types collected by a matching branch for its matched positions only, earlier types take precedence
+fn merge(types: Option<&mut Types>, branch: Types, positions: &HashSet<Position>) {
+    if let Some(types) = types {
+        for (position, name) in branch {
+            if positions.contains(&position) {
+                types.entry(position).or_insert(name);
+            }
+        }
+    }
+}
+
+// maps the position of each token which is not a separator to its field index
+fn fields(tokens: &[Token]) -> HashMap<Position, usize> {
+    tokens
+        .iter()
+        .filter(|token| !token.separator)
+        .enumerate()
+        .map(|(field, token)| (token.position, field))
+        .collect()
+}
+
+// groups consecutive positions into the positions of a value and the range of its first and last field; positions
+// are consecutive only for values spanning multiple tokens, as separators are matched only within such values
+fn values(positions: HashSet<Position>, fields: &HashMap<Position, usize>) -> Vec<Value> {
+    let mut positions: Vec<Position> = positions.into_iter().collect();
+    positions.sort();
+
+    let mut groups: Vec<Vec<Position>> = Vec::new();
+    for position in positions {
+        match groups.last_mut() {
+            Some(group) if group.last() == Some(&(position - 1)) => group.push(position),
+            _ => groups.push(vec![position]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            let first = *fields.get(group.first()?)?;
+            let last = *fields.get(group.last()?)?;
+            Some((group, (first, last)))
+        })
+        .collect()
+}
+
+pub fn matches<T, P>(terms: &[Term<T>], predicate: P) -> HashSet<Position>
+where
+    P: FnMut(&&Term<T>) -> bool,
+{
+    terms
+        .iter()
+        .filter(predicate)
+        .flat_map(|term| term.position..term.position + term.span)
+        .collect::<HashSet<Position>>()
+}
+
+#[cfg(test)]
+mod matches_tests {
+    use super::*;
+
+    #[test]
+    fn integer_matches() {
+        // setup
+        let integers = vec![
+            Term {
+                position: 2,
+                span: 1,
+                value: 1,
+            },
+            Term {
+                position: 4,
+                span: 1,
+                value: 2,
+            },
+            Term {
+                position: 6,
+                span: 1,
+                value: 3,
+            },
+        ];
+
+        // exercise
+        let integers_eq_integer_0 = matches(&integers, |term| term.value == 0);
+        let integers_eq_integer_2 = matches(&integers, |term| term.value == 2);
+        let integeres_ne_integer_0 = matches(&integers, |term| term.value != 0);
+        let integers_ne_integer_2 = matches(&integers, |term| term.value != 2);
+        let integers_gt_integer_0 = matches(&integers, |term| term.value > 0);
+        let integers_lt_integer_0 = matches(&integers, |term| term.value < 0);
+
+        // verify
+        assert_eq!(HashSet::from([]), integers_eq_integer_0);
+        assert_eq!(HashSet::from([4]), integers_eq_integer_2);
+        assert_eq!(HashSet::from([2, 4, 6]), integeres_ne_integer_0);
+        assert_eq!(HashSet::from([2, 6]), integers_ne_integer_2);
+        assert_eq!(HashSet::from([2, 4, 6]), integers_gt_integer_0);
+        assert_eq!(HashSet::from([]), integers_lt_integer_0);
+    }
+}
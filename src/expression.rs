@@ -1,379 +1,518 @@
 extern crate peg;
 
 use anyhow::{anyhow, Error};
-use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::format::{strftime::StrftimeItems, Item};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use regex::Regex;
 use semver::{Version, VersionReq};
-use std::collections::HashSet;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
-
+use std::str::FromStr;
+
+use crate::aggregation::{Aggregate, Aggregation, Function, Key};
+use crate::evaluator::select;
+use crate::evaluator::Correlation;
+use crate::evaluator::Count;
+use crate::evaluator::Expression;
+use crate::evaluator::Predicate;
+use crate::evaluator::Quantifier;
+use crate::evaluator::Selector;
+use crate::evaluator::Variable;
 use crate::filter::Formats;
+use crate::parser::literals;
+use crate::parser::relative;
 use crate::parser::FromWord;
 use crate::parser::Id;
 use crate::parser::Parser;
-use crate::parser::Term;
-use crate::tokenizer::Position;
+use crate::parser::TIMESTAMP_LITERALS;
+use crate::projection::Projection;
 use crate::tokenizer::Separators;
 use crate::tokenizer::Token;
 
 peg::parser!(pub grammar expression() for str {
-    pub rule evaluate(tokens: &Vec<Token>, formats: &Formats) -> HashSet<Position>
-        = or(tokens, formats)
+    pub rule compile(formats: &Formats) -> Expression
+        = _ expression:or(formats) _ { expression }
 
-    rule or(tokens: &Vec<Token>, formats: &Formats) -> HashSet<Position>
-        = l:and(tokens, formats) " or " r:and(tokens, formats) {
-            if !l.is_empty() || !r.is_empty() {
-                return l.union(&r).copied().collect();
-            }
+    rule or(formats: &Formats) -> Expression
+        = expressions:and(formats) ++ (_ "or" boundary() _) { Expression::or(expressions) }
 
-            return HashSet::new();
+    rule and(formats: &Formats) -> Expression
+        = expressions:correlation(formats) ++ (_ "and" boundary() _) { Expression::and(expressions) }
+
+    rule correlation(formats: &Formats) -> Expression
+        = first:not(formats) rest:(_ correlation:correlator() _ expression:not(formats) { (correlation, expression) })* {
+            Expression::correlation(first, rest)
         }
-        / and(tokens, formats)
 
-    rule and(tokens: &Vec<Token>, formats: &Formats) -> HashSet<Position>
-        = l:conditions(tokens, formats) " and " r:conditions(tokens, formats)  {
-            if !l.is_empty() && !r.is_empty() {
-                return l.union(&r).copied().collect();
-            }
+    rule correlator() -> Correlation
+        = "followed-by" boundary() { Correlation::FollowedBy }
+        / "within" __ distance:index() boundary() { Correlation::Within(distance) }
+        / "same-token" boundary() { Correlation::SameToken }
+
+    rule not(formats: &Formats) -> Expression
+        = ("not" boundary() / "!") _ expression:not(formats) { Expression::not(expression) }
+        / conditions(formats)
+
+    rule conditions(formats: &Formats) -> Expression
+        = quantifier(formats)
+        / condition(formats)
+        / "(" _ v:or(formats) _ ")" { v }
+
+    rule condition(formats: &Formats) -> Expression
+        = integer_condition()
+        / float_condition()
+        / id_condition()
+        / date_condition(formats)
+        / time_condition(formats)
+        / date_time_condition(formats)
+        / local_date_time_condition(formats)
+        / timestamp_condition(formats)
+        / ip_address_condition()
+        / ipv4_address_condition()
+        / ipv6_address_condition()
+        / ip_socket_address_condition()
+        / ipv4_socket_address_condition()
+        / ipv6_socket_address_condition()
+        / semantic_version_condition()
+        / ip_network_condition()
+        / ipv4_network_condition()
+        / ipv6_network_condition()
 
-            return HashSet::new();
+    //
+    // quantifiers
+    //
+    rule quantifier(formats: &Formats) -> Expression
+        = "all" _ "(" _ condition:condition(formats) _ ")" {? condition.quantify(Quantifier::All) }
+        / "none" _ "(" _ condition:quantified(formats) _ ")" {? condition.quantify(Quantifier::None) }
+        / "count" _ "(" _ condition:quantified(formats) _ ")" _ count:count() {? condition.quantify(Quantifier::Count(count)) }
+
+    rule quantified(formats: &Formats) -> Expression
+        = condition(formats)
+        / variable(formats)
+
+    rule count() -> Count
+        = "==" _ number:number() { Box::new(move |count| count == number) }
+        / "!=" _ number:number() { Box::new(move |count| count != number) }
+        / ">=" _ number:number() { Box::new(move |count| count >= number) }
+        / ">" _ number:number() { Box::new(move |count| count > number) }
+        / "<=" _ number:number() { Box::new(move |count| count <= number) }
+        / "<" _ number:number() { Box::new(move |count| count < number) }
+
+    rule variable(formats: &Formats) -> Expression
+        = integers:integers() boundary() { Expression::condition(integers, |_| true) }
+        / floats:floats() boundary() { Expression::condition(floats, |_| true) }
+        / ids:ids() boundary() { Expression::condition(ids, |_| true) }
+        / dates:dates(formats) boundary() { Expression::condition(dates, |_| true) }
+        / times:times(formats) boundary() { Expression::condition(times, |_| true) }
+        / date_times:date_times(formats) boundary() { Expression::condition(date_times, |_| true) }
+        / local_date_times:local_date_times(formats) boundary() { Expression::condition(local_date_times, |_| true) }
+        / timestamps:timestamps(formats) boundary() { Expression::condition(timestamps, |_| true) }
+        / ip_addresses:ip_addresses() boundary() { Expression::condition(ip_addresses, |_| true) }
+        / ipv4_addresses:ipv4_addresses() boundary() { Expression::condition(ipv4_addresses, |_| true) }
+        / ipv6_addresses:ipv6_addresses() boundary() { Expression::condition(ipv6_addresses, |_| true) }
+        / ip_socket_addresses:ip_socket_addresses() boundary() { Expression::condition(ip_socket_addresses, |_| true) }
+        / ipv4_socket_addresses:ipv4_socket_addresses() boundary() { Expression::condition(ipv4_socket_addresses, |_| true) }
+        / ipv6_socket_addresses:ipv6_socket_addresses() boundary() { Expression::condition(ipv6_socket_addresses, |_| true) }
+        / ip_networks:ip_networks() boundary() { Expression::condition(ip_networks, |_| true) }
+        / ipv4_networks:ipv4_networks() boundary() { Expression::condition(ipv4_networks, |_| true) }
+        / ipv6_networks:ipv6_networks() boundary() { Expression::condition(ipv6_networks, |_| true) }
+        / semantic_versions:semantic_versions() boundary() { Expression::condition(semantic_versions, |_| true) }
+
+    //
+    // projections
+    //
+    pub rule projection(formats: &Formats) -> Vec<Projection>
+        = _ projections:projected(formats) ++ (_ "," _) _ { projections }
+
+    rule projected(formats: &Formats) -> Projection
+        = ports:ip_socket_address_ports() { Projection::new(ports) }
+        / ports:ipv4_socket_address_ports() { Projection::new(ports) }
+        / ports:ipv6_socket_address_ports() { Projection::new(ports) }
+        / ips:ip_socket_address_ips() { Projection::new(ips) }
+        / ips:ipv4_socket_address_ips() { Projection::new(ips) }
+        / ips:ipv6_socket_address_ips() { Projection::new(ips) }
+        / integers:integers() boundary() { Projection::new(integers) }
+        / floats:floats() boundary() { Projection::new(floats) }
+        / ids:ids() boundary() { Projection::new(ids) }
+        / dates:dates(formats) boundary() { Projection::new(dates) }
+        / times:times(formats) boundary() { Projection::new(times) }
+        / date_times:date_times(formats) boundary() { Projection::new(date_times) }
+        / local_date_times:local_date_times(formats) boundary() { Projection::new(local_date_times) }
+        / timestamps:timestamps(formats) boundary() { Projection::new(timestamps) }
+        / ip_addresses:ip_addresses() boundary() { Projection::new(ip_addresses) }
+        / ipv4_addresses:ipv4_addresses() boundary() { Projection::new(ipv4_addresses) }
+        / ipv6_addresses:ipv6_addresses() boundary() { Projection::new(ipv6_addresses) }
+        / ip_socket_addresses:ip_socket_addresses() boundary() { Projection::new(ip_socket_addresses) }
+        / ipv4_socket_addresses:ipv4_socket_addresses() boundary() { Projection::new(ipv4_socket_addresses) }
+        / ipv6_socket_addresses:ipv6_socket_addresses() boundary() { Projection::new(ipv6_socket_addresses) }
+        / ip_networks:ip_networks() boundary() { Projection::new(ip_networks) }
+        / ipv4_networks:ipv4_networks() boundary() { Projection::new(ipv4_networks) }
+        / ipv6_networks:ipv6_networks() boundary() { Projection::new(ipv6_networks) }
+        / semantic_versions:semantic_versions() boundary() { Projection::new(semantic_versions) }
+
+    //
+    // aggregations
+    //
+    pub rule aggregation(formats: &Formats) -> Aggregation
+        = _ aggregates:aggregate() ++ (_ "," _) keys:(_ "by" boundary() _ keys:key(formats) ++ (_ "," _) { keys })? _ {
+            Aggregation::new(aggregates, keys.unwrap_or_default())
         }
-        / conditions(tokens, formats)
-
-    rule conditions(tokens: &Vec<Token>, formats: &Formats) -> HashSet<Position>
-        = condition(tokens, formats)
-        / "(" v:or(tokens, formats) ")" { v }
-
-    rule condition(tokens: &Vec<Token>, formats: &Formats) -> HashSet<Position>
-        = integer_condition(tokens)
-        / float_condition(tokens)
-        / id_condition(tokens)
-        / date_condition(tokens, formats)
-        / time_condition(tokens, formats)
-        / date_time_condition(tokens, formats)
-        / local_date_time_condition(tokens, formats)
-        / ip_address_condition(tokens)
-        / ipv4_address_condition(tokens)
-        / ipv6_address_condition(tokens)
-        / ip_socket_address_condition(tokens)
-        / ipv4_socket_address_condition(tokens)
-        / ipv6_socket_address_condition(tokens)
-        / semantic_version_condition(tokens)
-        / ip_network_condition(tokens)
-        / ipv4_network_condition(tokens)
-        / ipv6_network_condition(tokens)
+
+    rule aggregate() -> Aggregate
+        = label:&($("count" _ "(" _ ")")) "count" _ "(" _ ")" { Aggregate::new(label, Function::Count, None) }
+        / label:&($(function() _ "(" _ numbers() _ ")")) function:function() _ "(" _ numbers:numbers() _ ")" {
+            Aggregate::new(label, function, Some(numbers))
+        }
+
+    rule function() -> Function
+        = "sum" { Function::Sum }
+        / "min" { Function::Min }
+        / "max" { Function::Max }
+        / "avg" { Function::Avg }
+
+    rule numbers() -> Variable<f64>
+        = ports:ip_socket_address_ports() { ports.map(f64::from) }
+        / ports:ipv4_socket_address_ports() { ports.map(f64::from) }
+        / ports:ipv6_socket_address_ports() { ports.map(f64::from) }
+        / integers:integers() boundary() { integers.map(|integer| integer as f64) }
+        / floats:floats() boundary() { floats }
+
+    rule key(formats: &Formats) -> Key
+        = label:&($(projected(formats))) projection:projected(formats) { Key::new(label, projection) }
 
     //
     // conditions
     //
-    rule integer_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = integers:integers(tokens) " == " integer:integer() { matches(&integers, |term| term.value == integer) }
-    / integers:integers(tokens) " != " integer:integer() { matches(&integers, |term| term.value != integer) }
-    / integers:integers(tokens) " > " integer:integer() { matches(&integers, |term| term.value > integer) }
-    / integers:integers(tokens) " >= " integer:integer() { matches(&integers, |term| term.value >= integer) }
-    / integers:integers(tokens) " < " integer:integer() { matches(&integers, |term| term.value < integer) }
-    / integers:integers(tokens) " <= " integer:integer() { matches(&integers, |term| term.value <= integer) }
-
-    rule float_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = floats:floats(tokens) " == " float:float() { matches(&floats, |term| term.value == float) }
-    / floats:floats(tokens) " != " float:float() { matches(&floats, |term| term.value != float) }
-    / floats:floats(tokens) " > " float:float() { matches(&floats, |term| term.value > float) }
-    / floats:floats(tokens) " >= " float:float() { matches(&floats, |term| term.value >= float) }
-    / floats:floats(tokens) " < " float:float() { matches(&floats, |term| term.value < float) }
-    / floats:floats(tokens) " <= " float:float() { matches(&floats, |term| term.value <= float) }
-
-    rule id_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ids:ids(tokens) " == " id:id() { matches(&ids, |term| term.value == id) }
-    / ids:ids(tokens) " != " id:id() { matches(&ids, |term| term.value != id) }
-    / ids:ids(tokens) " > " id:id() { matches(&ids, |term| term.value > id) }
-    / ids:ids(tokens) " >= " id:id() { matches(&ids, |term| term.value >= id) }
-    / ids:ids(tokens) " < " id:id() { matches(&ids, |term| term.value < id) }
-    / ids:ids(tokens) " <= " id:id() { matches(&ids, |term| term.value <= id) }
-    / ids:ids(tokens) " contains " id:id() { matches(&ids, |term| term.value.contains(&id)) }
-    / ids:ids(tokens) " starts-with " id:id() { matches(&ids, |term| term.value.starts_with(&id)) }
-    / ids:ids(tokens) " ends-with " id:id() { matches(&ids, |term| term.value.ends_with(&id)) }
-
-    rule date_condition(tokens: &Vec<Token>, formats: &Formats) -> HashSet<Position>
-    = dates:dates(tokens, formats) " == " date:date(formats) { matches(&dates, |term| term.value == date) }
-    / dates:dates(tokens, formats) " != " date:date(formats) { matches(&dates, |term| term.value != date) }
-    / dates:dates(tokens, formats) " > " date:date(formats) { matches(&dates, |term| term.value > date) }
-    / dates:dates(tokens, formats) " >= " date:date(formats) { matches(&dates, |term| term.value >= date) }
-    / dates:dates(tokens, formats) " < " date:date(formats) { matches(&dates, |term| term.value < date) }
-    / dates:dates(tokens, formats) " <= " date:date(formats) { matches(&dates, |term| term.value <= date) }
-
-    rule time_condition(tokens: &Vec<Token>, formats: &Formats) -> HashSet<Position>
-    = times:times(tokens, formats) " == " time:time(formats) { matches(&times, |term| term.value == time) }
-    / times:times(tokens, formats) " != " time:time(formats) { matches(&times, |term| term.value != time) }
-    / times:times(tokens, formats) " > " time:time(formats) { matches(&times, |term| term.value > time) }
-    / times:times(tokens, formats) " >= " time:time(formats) { matches(&times, |term| term.value >= time) }
-    / times:times(tokens, formats) " < " time:time(formats) { matches(&times, |term| term.value < time) }
-    / times:times(tokens, formats) " <= " time:time(formats) { matches(&times, |term| term.value <= time) }
-
-    rule date_time_condition(tokens: &Vec<Token>, formats: &Formats) -> HashSet<Position>
-    = date_times:date_times(tokens, formats) " == " date_time:date_time(formats) { matches(&date_times, |term| term.value == date_time) }
-    / date_times:date_times(tokens, formats) " != " date_time:date_time(formats) { matches(&date_times, |term| term.value != date_time) }
-    / date_times:date_times(tokens, formats) " > " date_time:date_time(formats) { matches(&date_times, |term| term.value > date_time) }
-    / date_times:date_times(tokens, formats) " >= " date_time:date_time(formats) { matches(&date_times, |term| term.value >= date_time) }
-    / date_times:date_times(tokens, formats) " < " date_time:date_time(formats) { matches(&date_times, |term| term.value < date_time) }
-    / date_times:date_times(tokens, formats) " <= " date_time:date_time(formats) { matches(&date_times, |term| term.value <= date_time) }
-
-    rule local_date_time_condition(tokens: &Vec<Token>, formats: &Formats) -> HashSet<Position>
-    = local_date_times:local_date_times(tokens, formats) " == " local_date_time:local_date_time(formats) { matches(&local_date_times, |term| term.value == local_date_time) }
-    / local_date_times:local_date_times(tokens, formats) " != " local_date_time:local_date_time(formats) { matches(&local_date_times, |term| term.value != local_date_time) }
-    / local_date_times:local_date_times(tokens, formats) " > " local_date_time:local_date_time(formats) { matches(&local_date_times, |term| term.value > local_date_time) }
-    / local_date_times:local_date_times(tokens, formats) " >= " local_date_time:local_date_time(formats) { matches(&local_date_times, |term| term.value >= local_date_time) }
-    / local_date_times:local_date_times(tokens, formats) " < " local_date_time:local_date_time(formats) { matches(&local_date_times, |term| term.value < local_date_time) }
-    / local_date_times:local_date_times(tokens, formats) " <= " local_date_time:local_date_time(formats) { matches(&local_date_times, |term| term.value <= local_date_time) }
-
-    rule ip_address_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ip_addresses:ip_addresses(tokens) " == " ip_address:ip_address() { matches(&ip_addresses, |term| term.value == ip_address) }
-    / ip_addresses:ip_addresses(tokens) " != " ip_address:ip_address() { matches(&ip_addresses, |term| term.value != ip_address) }
-    / ip_addresses:ip_addresses(tokens) " > " ip_address:ip_address() { matches(&ip_addresses, |term| term.value > ip_address) }
-    / ip_addresses:ip_addresses(tokens) " >= " ip_address:ip_address() { matches(&ip_addresses, |term| term.value >= ip_address) }
-    / ip_addresses:ip_addresses(tokens) " < " ip_address:ip_address() { matches(&ip_addresses, |term| term.value < ip_address) }
-    / ip_addresses:ip_addresses(tokens) " <= " ip_address:ip_address() { matches(&ip_addresses, |term| term.value <= ip_address) }
-    / ip_addresses:ip_addresses(tokens) " in " ip_network:ip_network() { matches(&ip_addresses, |term| ip_network.contains(&term.value)) }
-    / ip_addresses:ip_addresses(tokens) " not in " ip_network:ip_network() { matches(&ip_addresses, |term| !ip_network.contains(&term.value)) }
-
-    rule ipv4_address_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ipv4_addresses:ipv4_addresses(tokens) " == " ipv4_address:ipv4_address() { matches(&ipv4_addresses, |term| term.value == ipv4_address) }
-    / ipv4_addresses:ipv4_addresses(tokens) " != " ipv4_address:ipv4_address() { matches(&ipv4_addresses, |term| term.value != ipv4_address) }
-    / ipv4_addresses:ipv4_addresses(tokens) " > " ipv4_address:ipv4_address() { matches(&ipv4_addresses, |term| term.value > ipv4_address) }
-    / ipv4_addresses:ipv4_addresses(tokens) " >= " ipv4_address:ipv4_address() { matches(&ipv4_addresses, |term| term.value >= ipv4_address) }
-    / ipv4_addresses:ipv4_addresses(tokens) " < " ipv4_address:ipv4_address() { matches(&ipv4_addresses, |term| term.value < ipv4_address) }
-    / ipv4_addresses:ipv4_addresses(tokens) " <= " ipv4_address:ipv4_address() { matches(&ipv4_addresses, |term| term.value <= ipv4_address) }
-    / ipv4_addresses:ipv4_addresses(tokens) " in " ipv4_network:ipv4_network() { matches(&ipv4_addresses, |term| ipv4_network.contains(&term.value)) }
-    / ipv4_addresses:ipv4_addresses(tokens) " not in " ipv4_network:ipv4_network() { matches(&ipv4_addresses, |term| !ipv4_network.contains(&term.value)) }
-
-    rule ipv6_address_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ipv6_addresses:ipv6_addresses(tokens) " == " ipv6_address:ipv6_address() { matches(&ipv6_addresses, |term| term.value == ipv6_address) }
-    / ipv6_addresses:ipv6_addresses(tokens) " != " ipv6_address:ipv6_address() { matches(&ipv6_addresses, |term| term.value != ipv6_address) }
-    / ipv6_addresses:ipv6_addresses(tokens) " > " ipv6_address:ipv6_address() { matches(&ipv6_addresses, |term| term.value > ipv6_address) }
-    / ipv6_addresses:ipv6_addresses(tokens) " >= " ipv6_address:ipv6_address() { matches(&ipv6_addresses, |term| term.value >= ipv6_address) }
-    / ipv6_addresses:ipv6_addresses(tokens) " < " ipv6_address:ipv6_address() { matches(&ipv6_addresses, |term| term.value < ipv6_address) }
-    / ipv6_addresses:ipv6_addresses(tokens) " <= " ipv6_address:ipv6_address() { matches(&ipv6_addresses, |term| term.value <= ipv6_address) }
-    / ipv6_addresses:ipv6_addresses(tokens) " in " ipv6_network:ipv6_network() { matches(&ipv6_addresses, |term| ipv6_network.contains(&term.value)) }
-    / ipv6_addresses:ipv6_addresses(tokens) " not in " ipv6_network:ipv6_network() { matches(&ipv6_addresses, |term| !ipv6_network.contains(&term.value)) }
-
-    rule ip_socket_address_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ip_socket_addresses:ip_socket_addresses(tokens) " == " ip_socket_address:ip_socket_address() { matches(&ip_socket_addresses, |term| term.value == ip_socket_address) }
-    / ip_socket_addresses:ip_socket_addresses(tokens) " != " ip_socket_address:ip_socket_address() { matches(&ip_socket_addresses, |term| term.value != ip_socket_address) }
-    / ip_socket_addresses:ip_socket_addresses(tokens) " > " ip_socket_address:ip_socket_address() { matches(&ip_socket_addresses, |term| term.value > ip_socket_address) }
-    / ip_socket_addresses:ip_socket_addresses(tokens) " >= " ip_socket_address:ip_socket_address() { matches(&ip_socket_addresses, |term| term.value >= ip_socket_address) }
-    / ip_socket_addresses:ip_socket_addresses(tokens) " < " ip_socket_address:ip_socket_address() { matches(&ip_socket_addresses, |term| term.value < ip_socket_address) }
-    / ip_socket_addresses:ip_socket_addresses(tokens) " <= " ip_socket_address:ip_socket_address() { matches(&ip_socket_addresses, |term| term.value <= ip_socket_address) }
-    / ip_socket_address_ports:ip_socket_address_ports(tokens) " == " port:port() { matches(&ip_socket_address_ports, |term| term.value == port) }
-    / ip_socket_address_ports:ip_socket_address_ports(tokens) " != " port:port() { matches(&ip_socket_address_ports, |term| term.value != port) }
-    / ip_socket_address_ports:ip_socket_address_ports(tokens) " > " port:port() { matches(&ip_socket_address_ports, |term| term.value > port) }
-    / ip_socket_address_ports:ip_socket_address_ports(tokens) " >= " port:port() { matches(&ip_socket_address_ports, |term| term.value >= port) }
-    / ip_socket_address_ports:ip_socket_address_ports(tokens) " < " port:port() { matches(&ip_socket_address_ports, |term| term.value < port) }
-    / ip_socket_address_ports:ip_socket_address_ports(tokens) " <= " port:port() { matches(&ip_socket_address_ports, |term| term.value <= port) }
-    / ip_socket_address_ips:ip_socket_address_ips(tokens) " == " ip_address:ip_address() { matches(&ip_socket_address_ips, |term| term.value == ip_address) }
-    / ip_socket_address_ips:ip_socket_address_ips(tokens) " != " ip_address:ip_address() { matches(&ip_socket_address_ips, |term| term.value != ip_address) }
-    / ip_socket_address_ips:ip_socket_address_ips(tokens) " > " ip_address:ip_address() { matches(&ip_socket_address_ips, |term| term.value > ip_address) }
-    / ip_socket_address_ips:ip_socket_address_ips(tokens) " >= " ip_address:ip_address() { matches(&ip_socket_address_ips, |term| term.value >= ip_address) }
-    / ip_socket_address_ips:ip_socket_address_ips(tokens) " < " ip_address:ip_address() { matches(&ip_socket_address_ips, |term| term.value < ip_address) }
-    / ip_socket_address_ips:ip_socket_address_ips(tokens) " <= " ip_address:ip_address() { matches(&ip_socket_address_ips, |term| term.value <= ip_address) }
-    / ip_socket_address_ips:ip_socket_address_ips(tokens) " in " ip_network:ip_network() { matches(&ip_socket_address_ips, |term| ip_network.contains(&term.value)) }
-    / ip_socket_address_ips:ip_socket_address_ips(tokens) " not in " ip_network:ip_network() { matches(&ip_socket_address_ips, |term| !ip_network.contains(&term.value)) }
-
-    rule ipv4_socket_address_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ipv4_socket_addresses:ipv4_socket_addresses(tokens) " == " ipv4_socket_address:ipv4_socket_address() { matches(&ipv4_socket_addresses, |term| term.value == ipv4_socket_address) }
-    / ipv4_socket_addresses:ipv4_socket_addresses(tokens) " != " ipv4_socket_address:ipv4_socket_address() { matches(&ipv4_socket_addresses, |term| term.value != ipv4_socket_address) }
-    / ipv4_socket_addresses:ipv4_socket_addresses(tokens) " > " ipv4_socket_address:ipv4_socket_address() { matches(&ipv4_socket_addresses, |term| term.value > ipv4_socket_address) }
-    / ipv4_socket_addresses:ipv4_socket_addresses(tokens) " >= " ipv4_socket_address:ipv4_socket_address() { matches(&ipv4_socket_addresses, |term| term.value >= ipv4_socket_address) }
-    / ipv4_socket_addresses:ipv4_socket_addresses(tokens) " < " ipv4_socket_address:ipv4_socket_address() { matches(&ipv4_socket_addresses, |term| term.value < ipv4_socket_address) }
-    / ipv4_socket_addresses:ipv4_socket_addresses(tokens) " <= " ipv4_socket_address:ipv4_socket_address() { matches(&ipv4_socket_addresses, |term| term.value <= ipv4_socket_address) }
-    / ipv4_socket_address_ports:ipv4_socket_address_ports(tokens) " == " port:port() { matches(&ipv4_socket_address_ports, |term| term.value == port) }
-    / ipv4_socket_address_ports:ipv4_socket_address_ports(tokens) " != " port:port() { matches(&ipv4_socket_address_ports, |term| term.value != port) }
-    / ipv4_socket_address_ports:ipv4_socket_address_ports(tokens) " > " port:port() { matches(&ipv4_socket_address_ports, |term| term.value > port) }
-    / ipv4_socket_address_ports:ipv4_socket_address_ports(tokens) " >= " port:port() { matches(&ipv4_socket_address_ports, |term| term.value >= port) }
-    / ipv4_socket_address_ports:ipv4_socket_address_ports(tokens) " < " port:port() { matches(&ipv4_socket_address_ports, |term| term.value < port) }
-    / ipv4_socket_address_ports:ipv4_socket_address_ports(tokens) " <= " port:port() { matches(&ipv4_socket_address_ports, |term| term.value <= port) }
-    / ipv4_socket_address_ips:ipv4_socket_address_ips(tokens) " == " ip_address:ip_address() { matches(&ipv4_socket_address_ips, |term| term.value == ip_address) }
-    / ipv4_socket_address_ips:ipv4_socket_address_ips(tokens) " != " ip_address:ip_address() { matches(&ipv4_socket_address_ips, |term| term.value != ip_address) }
-    / ipv4_socket_address_ips:ipv4_socket_address_ips(tokens) " > " ip_address:ip_address() { matches(&ipv4_socket_address_ips, |term| term.value > ip_address) }
-    / ipv4_socket_address_ips:ipv4_socket_address_ips(tokens) " >= " ip_address:ip_address() { matches(&ipv4_socket_address_ips, |term| term.value >= ip_address) }
-    / ipv4_socket_address_ips:ipv4_socket_address_ips(tokens) " < " ip_address:ip_address() { matches(&ipv4_socket_address_ips, |term| term.value < ip_address) }
-    / ipv4_socket_address_ips:ipv4_socket_address_ips(tokens) " <= " ip_address:ip_address() { matches(&ipv4_socket_address_ips, |term| term.value <= ip_address) }
-    / ipv4_socket_address_ips:ipv4_socket_address_ips(tokens) " in " ipv4_network:ipv4_network() { matches(&ipv4_socket_address_ips, |term| ipv4_network.contains(&term.value)) }
-    / ipv4_socket_address_ips:ipv4_socket_address_ips(tokens) " not in " ipv4_network:ipv4_network() { matches(&ipv4_socket_address_ips, |term| !ipv4_network.contains(&term.value)) }
-
-    rule ipv6_socket_address_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ipv6_socket_addresses:ipv6_socket_addresses(tokens) " == " ipv6_socket_address:ipv6_socket_address() { matches(&ipv6_socket_addresses, |term| term.value == ipv6_socket_address) }
-    / ipv6_socket_addresses:ipv6_socket_addresses(tokens) " != " ipv6_socket_address:ipv6_socket_address() { matches(&ipv6_socket_addresses, |term| term.value != ipv6_socket_address) }
-    / ipv6_socket_addresses:ipv6_socket_addresses(tokens) " > " ipv6_socket_address:ipv6_socket_address() { matches(&ipv6_socket_addresses, |term| term.value > ipv6_socket_address) }
-    / ipv6_socket_addresses:ipv6_socket_addresses(tokens) " >= " ipv6_socket_address:ipv6_socket_address() { matches(&ipv6_socket_addresses, |term| term.value >= ipv6_socket_address) }
-    / ipv6_socket_addresses:ipv6_socket_addresses(tokens) " < " ipv6_socket_address:ipv6_socket_address() { matches(&ipv6_socket_addresses, |term| term.value < ipv6_socket_address) }
-    / ipv6_socket_addresses:ipv6_socket_addresses(tokens) " <= " ipv6_socket_address:ipv6_socket_address() { matches(&ipv6_socket_addresses, |term| term.value <= ipv6_socket_address) }
-    / ipv6_socket_address_ports:ipv6_socket_address_ports(tokens) " == " port:port() { matches(&ipv6_socket_address_ports, |term| term.value == port) }
-    / ipv6_socket_address_ports:ipv6_socket_address_ports(tokens) " != " port:port() { matches(&ipv6_socket_address_ports, |term| term.value != port) }
-    / ipv6_socket_address_ports:ipv6_socket_address_ports(tokens) " > " port:port() { matches(&ipv6_socket_address_ports, |term| term.value > port) }
-    / ipv6_socket_address_ports:ipv6_socket_address_ports(tokens) " >= " port:port() { matches(&ipv6_socket_address_ports, |term| term.value >= port) }
-    / ipv6_socket_address_ports:ipv6_socket_address_ports(tokens) " < " port:port() { matches(&ipv6_socket_address_ports, |term| term.value < port) }
-    / ipv6_socket_address_ports:ipv6_socket_address_ports(tokens) " <= " port:port() { matches(&ipv6_socket_address_ports, |term| term.value <= port) }
-    / ipv6_socket_address_ips:ipv6_socket_address_ips(tokens) " == " ip_address:ip_address() { matches(&ipv6_socket_address_ips, |term| term.value == ip_address) }
-    / ipv6_socket_address_ips:ipv6_socket_address_ips(tokens) " != " ip_address:ip_address() { matches(&ipv6_socket_address_ips, |term| term.value != ip_address) }
-    / ipv6_socket_address_ips:ipv6_socket_address_ips(tokens) " > " ip_address:ip_address() { matches(&ipv6_socket_address_ips, |term| term.value > ip_address) }
-    / ipv6_socket_address_ips:ipv6_socket_address_ips(tokens) " >= " ip_address:ip_address() { matches(&ipv6_socket_address_ips, |term| term.value >= ip_address) }
-    / ipv6_socket_address_ips:ipv6_socket_address_ips(tokens) " < " ip_address:ip_address() { matches(&ipv6_socket_address_ips, |term| term.value < ip_address) }
-    / ipv6_socket_address_ips:ipv6_socket_address_ips(tokens) " <= " ip_address:ip_address() { matches(&ipv6_socket_address_ips, |term| term.value <= ip_address) }
-    / ipv6_socket_address_ips:ipv6_socket_address_ips(tokens) " in " ipv6_network:ipv6_network() { matches(&ipv6_socket_address_ips, |term| ipv6_network.contains(&term.value)) }
-    / ipv6_socket_address_ips:ipv6_socket_address_ips(tokens) " not in " ipv6_network:ipv6_network() { matches(&ipv6_socket_address_ips, |term| !ipv6_network.contains(&term.value)) }
-
-    rule ip_network_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ip_networks:ip_networks(tokens) " == " ip_network:ip_network() { matches(&ip_networks, |term| term.value == ip_network) }
-    / ip_networks:ip_networks(tokens) " != " ip_network:ip_network() { matches(&ip_networks, |term| term.value != ip_network) }
-    / ip_networks:ip_networks(tokens) " > " ip_network:ip_network() { matches(&ip_networks, |term| term.value > ip_network) }
-    / ip_networks:ip_networks(tokens) " >= " ip_network:ip_network() { matches(&ip_networks, |term| term.value >= ip_network) }
-    / ip_networks:ip_networks(tokens) " < " ip_network:ip_network() { matches(&ip_networks, |term| term.value < ip_network) }
-    / ip_networks:ip_networks(tokens) " <= " ip_network:ip_network() { matches(&ip_networks, |term| term.value <= ip_network) }
-
-    rule ipv4_network_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ipv4_networks:ipv4_networks(tokens) " == " ipv4_network:ipv4_network() { matches(&ipv4_networks, |term| term.value == ipv4_network) }
-    / ipv4_networks:ipv4_networks(tokens) " != " ipv4_network:ipv4_network() { matches(&ipv4_networks, |term| term.value != ipv4_network) }
-    / ipv4_networks:ipv4_networks(tokens) " > " ipv4_network:ipv4_network() { matches(&ipv4_networks, |term| term.value > ipv4_network) }
-    / ipv4_networks:ipv4_networks(tokens) " >= " ipv4_network:ipv4_network() { matches(&ipv4_networks, |term| term.value >= ipv4_network) }
-    / ipv4_networks:ipv4_networks(tokens) " < " ipv4_network:ipv4_network() { matches(&ipv4_networks, |term| term.value < ipv4_network) }
-    / ipv4_networks:ipv4_networks(tokens) " <= " ipv4_network:ipv4_network() { matches(&ipv4_networks, |term| term.value <= ipv4_network) }
-
-    rule ipv6_network_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = ipv6_networks:ipv6_networks(tokens) " == " ipv6_network:ipv6_network() { matches(&ipv6_networks, |term| term.value == ipv6_network) }
-    / ipv6_networks:ipv6_networks(tokens) " != " ipv6_network:ipv6_network() { matches(&ipv6_networks, |term| term.value != ipv6_network) }
-    / ipv6_networks:ipv6_networks(tokens) " > " ipv6_network:ipv6_network() { matches(&ipv6_networks, |term| term.value > ipv6_network) }
-    / ipv6_networks:ipv6_networks(tokens) " >= " ipv6_network:ipv6_network() { matches(&ipv6_networks, |term| term.value >= ipv6_network) }
-    / ipv6_networks:ipv6_networks(tokens) " < " ipv6_network:ipv6_network() { matches(&ipv6_networks, |term| term.value < ipv6_network) }
-    / ipv6_networks:ipv6_networks(tokens) " <= " ipv6_network:ipv6_network() { matches(&ipv6_networks, |term| term.value <= ipv6_network) }
-
-    rule semantic_version_condition(tokens: &Vec<Token>) -> HashSet<Position>
-    = semantic_versions:semantic_versions(tokens) " == " semantic_version:semantic_version() { matches(&semantic_versions, |term| term.value == semantic_version) }
-    / semantic_versions:semantic_versions(tokens) " != " semantic_version:semantic_version() { matches(&semantic_versions, |term| term.value != semantic_version) }
-    / semantic_versions:semantic_versions(tokens) " > " semantic_version:semantic_version() { matches(&semantic_versions, |term| term.value > semantic_version) }
-    / semantic_versions:semantic_versions(tokens) " >= " semantic_version:semantic_version() { matches(&semantic_versions, |term| term.value >= semantic_version) }
-    / semantic_versions:semantic_versions(tokens) " < " semantic_version:semantic_version() { matches(&semantic_versions, |term| term.value < semantic_version) }
-    / semantic_versions:semantic_versions(tokens) " <= " semantic_version:semantic_version() { matches(&semantic_versions, |term| term.value <= semantic_version) }
-    / semantic_versions:semantic_versions(tokens) " matches " semantic_version_requirement:semantic_version_requirement() { matches(&semantic_versions, |term| semantic_version_requirement.matches(&term.value)) }
+    rule integer_condition() -> Expression
+    = integers:integers() _ "==" _ integer:integer() { Expression::condition(integers, move |value| *value == integer) }
+    / integers:integers() _ "!=" _ integer:integer() { Expression::condition(integers, move |value| *value != integer) }
+    / integers:integers() _ ">" _ integer:integer() { Expression::condition(integers, move |value| *value > integer) }
+    / integers:integers() _ ">=" _ integer:integer() { Expression::condition(integers, move |value| *value >= integer) }
+    / integers:integers() _ "<" _ integer:integer() { Expression::condition(integers, move |value| *value < integer) }
+    / integers:integers() _ "<=" _ integer:integer() { Expression::condition(integers, move |value| *value <= integer) }
+
+    rule float_condition() -> Expression
+    = floats:floats() _ "==" _ float:float() { Expression::condition(floats, move |value| *value == float) }
+    / floats:floats() _ "!=" _ float:float() { Expression::condition(floats, move |value| *value != float) }
+    / floats:floats() _ ">" _ float:float() { Expression::condition(floats, move |value| *value > float) }
+    / floats:floats() _ ">=" _ float:float() { Expression::condition(floats, move |value| *value >= float) }
+    / floats:floats() _ "<" _ float:float() { Expression::condition(floats, move |value| *value < float) }
+    / floats:floats() _ "<=" _ float:float() { Expression::condition(floats, move |value| *value <= float) }
+
+    rule id_condition() -> Expression
+    = fields:fields() predicate:id_predicate(<word()>) { Expression::condition(fields, predicate) }
+    / ids:ids() predicate:id_predicate(<text()>) { Expression::condition(ids, predicate) }
+
+    rule id_predicate(value: rule<Id>) -> Predicate<Id>
+    = _ "==" _ id:value() { Box::new(move |value: &Id| *value == id) }
+    / _ "!=" _ id:value() { Box::new(move |value: &Id| *value != id) }
+    / _ ">" _ id:value() { Box::new(move |value: &Id| *value > id) }
+    / _ ">=" _ id:value() { Box::new(move |value: &Id| *value >= id) }
+    / _ "<" _ id:value() { Box::new(move |value: &Id| *value < id) }
+    / _ "<=" _ id:value() { Box::new(move |value: &Id| *value <= id) }
+    / __ "contains" boundary() _ id:value() { Box::new(move |value: &Id| value.contains(&id)) }
+    / __ "starts-with" boundary() _ id:value() { Box::new(move |value: &Id| value.starts_with(&id)) }
+    / __ "ends-with" boundary() _ id:value() { Box::new(move |value: &Id| value.ends_with(&id)) }
+    / _ "=~" _ regex:regex() { Box::new(move |value: &Id| regex.is_match(value)) }
+    / _ "!~" _ regex:regex() { Box::new(move |value: &Id| !regex.is_match(value)) }
+    / __ "matches-regex" boundary() _ regex:regex() { Box::new(move |value: &Id| regex.is_match(value)) }
+
+    rule date_condition(formats: &Formats) -> Expression
+    = dates:dates(formats) _ "==" _ date:date(formats) { Expression::condition(dates, move |value| *value == date) }
+    / dates:dates(formats) _ "!=" _ date:date(formats) { Expression::condition(dates, move |value| *value != date) }
+    / dates:dates(formats) _ ">" _ date:date(formats) { Expression::condition(dates, move |value| *value > date) }
+    / dates:dates(formats) _ ">=" _ date:date(formats) { Expression::condition(dates, move |value| *value >= date) }
+    / dates:dates(formats) _ "<" _ date:date(formats) { Expression::condition(dates, move |value| *value < date) }
+    / dates:dates(formats) _ "<=" _ date:date(formats) { Expression::condition(dates, move |value| *value <= date) }
+
+    rule time_condition(formats: &Formats) -> Expression
+    = times:times(formats) _ "==" _ time:time(formats) { Expression::condition(times, move |value| *value == time) }
+    / times:times(formats) _ "!=" _ time:time(formats) { Expression::condition(times, move |value| *value != time) }
+    / times:times(formats) _ ">" _ time:time(formats) { Expression::condition(times, move |value| *value > time) }
+    / times:times(formats) _ ">=" _ time:time(formats) { Expression::condition(times, move |value| *value >= time) }
+    / times:times(formats) _ "<" _ time:time(formats) { Expression::condition(times, move |value| *value < time) }
+    / times:times(formats) _ "<=" _ time:time(formats) { Expression::condition(times, move |value| *value <= time) }
+
+    rule date_time_condition(formats: &Formats) -> Expression
+    = date_times:date_times(formats) _ "==" _ date_time:date_time(formats) { Expression::condition(date_times, move |value| *value == date_time) }
+    / date_times:date_times(formats) _ "!=" _ date_time:date_time(formats) { Expression::condition(date_times, move |value| *value != date_time) }
+    / date_times:date_times(formats) _ ">" _ date_time:date_time(formats) { Expression::condition(date_times, move |value| *value > date_time) }
+    / date_times:date_times(formats) _ ">=" _ date_time:date_time(formats) { Expression::condition(date_times, move |value| *value >= date_time) }
+    / date_times:date_times(formats) _ "<" _ date_time:date_time(formats) { Expression::condition(date_times, move |value| *value < date_time) }
+    / date_times:date_times(formats) _ "<=" _ date_time:date_time(formats) { Expression::condition(date_times, move |value| *value <= date_time) }
+
+    rule local_date_time_condition(formats: &Formats) -> Expression
+    = local_date_times:local_date_times(formats) _ "==" _ local_date_time:local_date_time(formats) { Expression::condition(local_date_times, move |value| *value == local_date_time) }
+    / local_date_times:local_date_times(formats) _ "!=" _ local_date_time:local_date_time(formats) { Expression::condition(local_date_times, move |value| *value != local_date_time) }
+    / local_date_times:local_date_times(formats) _ ">" _ local_date_time:local_date_time(formats) { Expression::condition(local_date_times, move |value| *value > local_date_time) }
+    / local_date_times:local_date_times(formats) _ ">=" _ local_date_time:local_date_time(formats) { Expression::condition(local_date_times, move |value| *value >= local_date_time) }
+    / local_date_times:local_date_times(formats) _ "<" _ local_date_time:local_date_time(formats) { Expression::condition(local_date_times, move |value| *value < local_date_time) }
+    / local_date_times:local_date_times(formats) _ "<=" _ local_date_time:local_date_time(formats) { Expression::condition(local_date_times, move |value| *value <= local_date_time) }
+
+    rule timestamp_condition(formats: &Formats) -> Expression
+    = timestamps:timestamps(formats) _ "==" _ timestamp:timestamp(formats) { Expression::condition(timestamps, move |value| *value == timestamp) }
+    / timestamps:timestamps(formats) _ "!=" _ timestamp:timestamp(formats) { Expression::condition(timestamps, move |value| *value != timestamp) }
+    / timestamps:timestamps(formats) _ ">" _ timestamp:timestamp(formats) { Expression::condition(timestamps, move |value| *value > timestamp) }
+    / timestamps:timestamps(formats) _ ">=" _ timestamp:timestamp(formats) { Expression::condition(timestamps, move |value| *value >= timestamp) }
+    / timestamps:timestamps(formats) _ "<" _ timestamp:timestamp(formats) { Expression::condition(timestamps, move |value| *value < timestamp) }
+    / timestamps:timestamps(formats) _ "<=" _ timestamp:timestamp(formats) { Expression::condition(timestamps, move |value| *value <= timestamp) }
+
+    rule ip_address_condition() -> Expression
+    = ip_addresses:ip_addresses() _ "==" _ ip_address:ip_address() { Expression::condition(ip_addresses, move |value| *value == ip_address) }
+    / ip_addresses:ip_addresses() _ "!=" _ ip_address:ip_address() { Expression::condition(ip_addresses, move |value| *value != ip_address) }
+    / ip_addresses:ip_addresses() _ ">" _ ip_address:ip_address() { Expression::condition(ip_addresses, move |value| *value > ip_address) }
+    / ip_addresses:ip_addresses() _ ">=" _ ip_address:ip_address() { Expression::condition(ip_addresses, move |value| *value >= ip_address) }
+    / ip_addresses:ip_addresses() _ "<" _ ip_address:ip_address() { Expression::condition(ip_addresses, move |value| *value < ip_address) }
+    / ip_addresses:ip_addresses() _ "<=" _ ip_address:ip_address() { Expression::condition(ip_addresses, move |value| *value <= ip_address) }
+    / ip_addresses:ip_addresses() __ "in" boundary() _ ip_network:ip_network() { Expression::condition(ip_addresses, move |value| ip_network.contains(value)) }
+    / ip_addresses:ip_addresses() __ "not" __ "in" boundary() _ ip_network:ip_network() { Expression::condition(ip_addresses, move |value| !ip_network.contains(value)) }
+
+    rule ipv4_address_condition() -> Expression
+    = ipv4_addresses:ipv4_addresses() _ "==" _ ipv4_address:ipv4_address() { Expression::condition(ipv4_addresses, move |value| *value == ipv4_address) }
+    / ipv4_addresses:ipv4_addresses() _ "!=" _ ipv4_address:ipv4_address() { Expression::condition(ipv4_addresses, move |value| *value != ipv4_address) }
+    / ipv4_addresses:ipv4_addresses() _ ">" _ ipv4_address:ipv4_address() { Expression::condition(ipv4_addresses, move |value| *value > ipv4_address) }
+    / ipv4_addresses:ipv4_addresses() _ ">=" _ ipv4_address:ipv4_address() { Expression::condition(ipv4_addresses, move |value| *value >= ipv4_address) }
+    / ipv4_addresses:ipv4_addresses() _ "<" _ ipv4_address:ipv4_address() { Expression::condition(ipv4_addresses, move |value| *value < ipv4_address) }
+    / ipv4_addresses:ipv4_addresses() _ "<=" _ ipv4_address:ipv4_address() { Expression::condition(ipv4_addresses, move |value| *value <= ipv4_address) }
+    / ipv4_addresses:ipv4_addresses() __ "in" boundary() _ ipv4_network:ipv4_network() { Expression::condition(ipv4_addresses, move |value| ipv4_network.contains(value)) }
+    / ipv4_addresses:ipv4_addresses() __ "not" __ "in" boundary() _ ipv4_network:ipv4_network() { Expression::condition(ipv4_addresses, move |value| !ipv4_network.contains(value)) }
+
+    rule ipv6_address_condition() -> Expression
+    = ipv6_addresses:ipv6_addresses() _ "==" _ ipv6_address:ipv6_address() { Expression::condition(ipv6_addresses, move |value| *value == ipv6_address) }
+    / ipv6_addresses:ipv6_addresses() _ "!=" _ ipv6_address:ipv6_address() { Expression::condition(ipv6_addresses, move |value| *value != ipv6_address) }
+    / ipv6_addresses:ipv6_addresses() _ ">" _ ipv6_address:ipv6_address() { Expression::condition(ipv6_addresses, move |value| *value > ipv6_address) }
+    / ipv6_addresses:ipv6_addresses() _ ">=" _ ipv6_address:ipv6_address() { Expression::condition(ipv6_addresses, move |value| *value >= ipv6_address) }
+    / ipv6_addresses:ipv6_addresses() _ "<" _ ipv6_address:ipv6_address() { Expression::condition(ipv6_addresses, move |value| *value < ipv6_address) }
+    / ipv6_addresses:ipv6_addresses() _ "<=" _ ipv6_address:ipv6_address() { Expression::condition(ipv6_addresses, move |value| *value <= ipv6_address) }
+    / ipv6_addresses:ipv6_addresses() __ "in" boundary() _ ipv6_network:ipv6_network() { Expression::condition(ipv6_addresses, move |value| ipv6_network.contains(value)) }
+    / ipv6_addresses:ipv6_addresses() __ "not" __ "in" boundary() _ ipv6_network:ipv6_network() { Expression::condition(ipv6_addresses, move |value| !ipv6_network.contains(value)) }
+
+    rule ip_socket_address_condition() -> Expression
+    = ip_socket_addresses:ip_socket_addresses() _ "==" _ ip_socket_address:ip_socket_address() { Expression::condition(ip_socket_addresses, move |value| *value == ip_socket_address) }
+    / ip_socket_addresses:ip_socket_addresses() _ "!=" _ ip_socket_address:ip_socket_address() { Expression::condition(ip_socket_addresses, move |value| *value != ip_socket_address) }
+    / ip_socket_addresses:ip_socket_addresses() _ ">" _ ip_socket_address:ip_socket_address() { Expression::condition(ip_socket_addresses, move |value| *value > ip_socket_address) }
+    / ip_socket_addresses:ip_socket_addresses() _ ">=" _ ip_socket_address:ip_socket_address() { Expression::condition(ip_socket_addresses, move |value| *value >= ip_socket_address) }
+    / ip_socket_addresses:ip_socket_addresses() _ "<" _ ip_socket_address:ip_socket_address() { Expression::condition(ip_socket_addresses, move |value| *value < ip_socket_address) }
+    / ip_socket_addresses:ip_socket_addresses() _ "<=" _ ip_socket_address:ip_socket_address() { Expression::condition(ip_socket_addresses, move |value| *value <= ip_socket_address) }
+    / ip_socket_address_ports:ip_socket_address_ports() _ "==" _ port:port() { Expression::condition(ip_socket_address_ports, move |value| *value == port) }
+    / ip_socket_address_ports:ip_socket_address_ports() _ "!=" _ port:port() { Expression::condition(ip_socket_address_ports, move |value| *value != port) }
+    / ip_socket_address_ports:ip_socket_address_ports() _ ">" _ port:port() { Expression::condition(ip_socket_address_ports, move |value| *value > port) }
+    / ip_socket_address_ports:ip_socket_address_ports() _ ">=" _ port:port() { Expression::condition(ip_socket_address_ports, move |value| *value >= port) }
+    / ip_socket_address_ports:ip_socket_address_ports() _ "<" _ port:port() { Expression::condition(ip_socket_address_ports, move |value| *value < port) }
+    / ip_socket_address_ports:ip_socket_address_ports() _ "<=" _ port:port() { Expression::condition(ip_socket_address_ports, move |value| *value <= port) }
+    / ip_socket_address_ips:ip_socket_address_ips() _ "==" _ ip_address:ip_address() { Expression::condition(ip_socket_address_ips, move |value| *value == ip_address) }
+    / ip_socket_address_ips:ip_socket_address_ips() _ "!=" _ ip_address:ip_address() { Expression::condition(ip_socket_address_ips, move |value| *value != ip_address) }
+    / ip_socket_address_ips:ip_socket_address_ips() _ ">" _ ip_address:ip_address() { Expression::condition(ip_socket_address_ips, move |value| *value > ip_address) }
+    / ip_socket_address_ips:ip_socket_address_ips() _ ">=" _ ip_address:ip_address() { Expression::condition(ip_socket_address_ips, move |value| *value >= ip_address) }
+    / ip_socket_address_ips:ip_socket_address_ips() _ "<" _ ip_address:ip_address() { Expression::condition(ip_socket_address_ips, move |value| *value < ip_address) }
+    / ip_socket_address_ips:ip_socket_address_ips() _ "<=" _ ip_address:ip_address() { Expression::condition(ip_socket_address_ips, move |value| *value <= ip_address) }
+    / ip_socket_address_ips:ip_socket_address_ips() __ "in" boundary() _ ip_network:ip_network() { Expression::condition(ip_socket_address_ips, move |value| ip_network.contains(value)) }
+    / ip_socket_address_ips:ip_socket_address_ips() __ "not" __ "in" boundary() _ ip_network:ip_network() { Expression::condition(ip_socket_address_ips, move |value| !ip_network.contains(value)) }
+
+    rule ipv4_socket_address_condition() -> Expression
+    = ipv4_socket_addresses:ipv4_socket_addresses() _ "==" _ ipv4_socket_address:ipv4_socket_address() { Expression::condition(ipv4_socket_addresses, move |value| *value == ipv4_socket_address) }
+    / ipv4_socket_addresses:ipv4_socket_addresses() _ "!=" _ ipv4_socket_address:ipv4_socket_address() { Expression::condition(ipv4_socket_addresses, move |value| *value != ipv4_socket_address) }
+    / ipv4_socket_addresses:ipv4_socket_addresses() _ ">" _ ipv4_socket_address:ipv4_socket_address() { Expression::condition(ipv4_socket_addresses, move |value| *value > ipv4_socket_address) }
+    / ipv4_socket_addresses:ipv4_socket_addresses() _ ">=" _ ipv4_socket_address:ipv4_socket_address() { Expression::condition(ipv4_socket_addresses, move |value| *value >= ipv4_socket_address) }
+    / ipv4_socket_addresses:ipv4_socket_addresses() _ "<" _ ipv4_socket_address:ipv4_socket_address() { Expression::condition(ipv4_socket_addresses, move |value| *value < ipv4_socket_address) }
+    / ipv4_socket_addresses:ipv4_socket_addresses() _ "<=" _ ipv4_socket_address:ipv4_socket_address() { Expression::condition(ipv4_socket_addresses, move |value| *value <= ipv4_socket_address) }
+    / ipv4_socket_address_ports:ipv4_socket_address_ports() _ "==" _ port:port() { Expression::condition(ipv4_socket_address_ports, move |value| *value == port) }
+    / ipv4_socket_address_ports:ipv4_socket_address_ports() _ "!=" _ port:port() { Expression::condition(ipv4_socket_address_ports, move |value| *value != port) }
+    / ipv4_socket_address_ports:ipv4_socket_address_ports() _ ">" _ port:port() { Expression::condition(ipv4_socket_address_ports, move |value| *value > port) }
+    / ipv4_socket_address_ports:ipv4_socket_address_ports() _ ">=" _ port:port() { Expression::condition(ipv4_socket_address_ports, move |value| *value >= port) }
+    / ipv4_socket_address_ports:ipv4_socket_address_ports() _ "<" _ port:port() { Expression::condition(ipv4_socket_address_ports, move |value| *value < port) }
+    / ipv4_socket_address_ports:ipv4_socket_address_ports() _ "<=" _ port:port() { Expression::condition(ipv4_socket_address_ports, move |value| *value <= port) }
+    / ipv4_socket_address_ips:ipv4_socket_address_ips() _ "==" _ ip_address:ip_address() { Expression::condition(ipv4_socket_address_ips, move |value| *value == ip_address) }
+    / ipv4_socket_address_ips:ipv4_socket_address_ips() _ "!=" _ ip_address:ip_address() { Expression::condition(ipv4_socket_address_ips, move |value| *value != ip_address) }
+    / ipv4_socket_address_ips:ipv4_socket_address_ips() _ ">" _ ip_address:ip_address() { Expression::condition(ipv4_socket_address_ips, move |value| *value > ip_address) }
+    / ipv4_socket_address_ips:ipv4_socket_address_ips() _ ">=" _ ip_address:ip_address() { Expression::condition(ipv4_socket_address_ips, move |value| *value >= ip_address) }
+    / ipv4_socket_address_ips:ipv4_socket_address_ips() _ "<" _ ip_address:ip_address() { Expression::condition(ipv4_socket_address_ips, move |value| *value < ip_address) }
+    / ipv4_socket_address_ips:ipv4_socket_address_ips() _ "<=" _ ip_address:ip_address() { Expression::condition(ipv4_socket_address_ips, move |value| *value <= ip_address) }
+    / ipv4_socket_address_ips:ipv4_socket_address_ips() __ "in" boundary() _ ipv4_network:ipv4_network() { Expression::condition(ipv4_socket_address_ips, move |value| ipv4_network.contains(value)) }
+    / ipv4_socket_address_ips:ipv4_socket_address_ips() __ "not" __ "in" boundary() _ ipv4_network:ipv4_network() { Expression::condition(ipv4_socket_address_ips, move |value| !ipv4_network.contains(value)) }
+
+    rule ipv6_socket_address_condition() -> Expression
+    = ipv6_socket_addresses:ipv6_socket_addresses() _ "==" _ ipv6_socket_address:ipv6_socket_address() { Expression::condition(ipv6_socket_addresses, move |value| *value == ipv6_socket_address) }
+    / ipv6_socket_addresses:ipv6_socket_addresses() _ "!=" _ ipv6_socket_address:ipv6_socket_address() { Expression::condition(ipv6_socket_addresses, move |value| *value != ipv6_socket_address) }
+    / ipv6_socket_addresses:ipv6_socket_addresses() _ ">" _ ipv6_socket_address:ipv6_socket_address() { Expression::condition(ipv6_socket_addresses, move |value| *value > ipv6_socket_address) }
+    / ipv6_socket_addresses:ipv6_socket_addresses() _ ">=" _ ipv6_socket_address:ipv6_socket_address() { Expression::condition(ipv6_socket_addresses, move |value| *value >= ipv6_socket_address) }
+    / ipv6_socket_addresses:ipv6_socket_addresses() _ "<" _ ipv6_socket_address:ipv6_socket_address() { Expression::condition(ipv6_socket_addresses, move |value| *value < ipv6_socket_address) }
+    / ipv6_socket_addresses:ipv6_socket_addresses() _ "<=" _ ipv6_socket_address:ipv6_socket_address() { Expression::condition(ipv6_socket_addresses, move |value| *value <= ipv6_socket_address) }
+    / ipv6_socket_address_ports:ipv6_socket_address_ports() _ "==" _ port:port() { Expression::condition(ipv6_socket_address_ports, move |value| *value == port) }
+    / ipv6_socket_address_ports:ipv6_socket_address_ports() _ "!=" _ port:port() { Expression::condition(ipv6_socket_address_ports, move |value| *value != port) }
+    / ipv6_socket_address_ports:ipv6_socket_address_ports() _ ">" _ port:port() { Expression::condition(ipv6_socket_address_ports, move |value| *value > port) }
+    / ipv6_socket_address_ports:ipv6_socket_address_ports() _ ">=" _ port:port() { Expression::condition(ipv6_socket_address_ports, move |value| *value >= port) }
+    / ipv6_socket_address_ports:ipv6_socket_address_ports() _ "<" _ port:port() { Expression::condition(ipv6_socket_address_ports, move |value| *value < port) }
+    / ipv6_socket_address_ports:ipv6_socket_address_ports() _ "<=" _ port:port() { Expression::condition(ipv6_socket_address_ports, move |value| *value <= port) }
+    / ipv6_socket_address_ips:ipv6_socket_address_ips() _ "==" _ ip_address:ip_address() { Expression::condition(ipv6_socket_address_ips, move |value| *value == ip_address) }
+    / ipv6_socket_address_ips:ipv6_socket_address_ips() _ "!=" _ ip_address:ip_address() { Expression::condition(ipv6_socket_address_ips, move |value| *value != ip_address) }
+    / ipv6_socket_address_ips:ipv6_socket_address_ips() _ ">" _ ip_address:ip_address() { Expression::condition(ipv6_socket_address_ips, move |value| *value > ip_address) }
+    / ipv6_socket_address_ips:ipv6_socket_address_ips() _ ">=" _ ip_address:ip_address() { Expression::condition(ipv6_socket_address_ips, move |value| *value >= ip_address) }
+    / ipv6_socket_address_ips:ipv6_socket_address_ips() _ "<" _ ip_address:ip_address() { Expression::condition(ipv6_socket_address_ips, move |value| *value < ip_address) }
+    / ipv6_socket_address_ips:ipv6_socket_address_ips() _ "<=" _ ip_address:ip_address() { Expression::condition(ipv6_socket_address_ips, move |value| *value <= ip_address) }
+    / ipv6_socket_address_ips:ipv6_socket_address_ips() __ "in" boundary() _ ipv6_network:ipv6_network() { Expression::condition(ipv6_socket_address_ips, move |value| ipv6_network.contains(value)) }
+    / ipv6_socket_address_ips:ipv6_socket_address_ips() __ "not" __ "in" boundary() _ ipv6_network:ipv6_network() { Expression::condition(ipv6_socket_address_ips, move |value| !ipv6_network.contains(value)) }
+
+    rule ip_network_condition() -> Expression
+    = ip_networks:ip_networks() _ "==" _ ip_network:ip_network() { Expression::condition(ip_networks, move |value| *value == ip_network) }
+    / ip_networks:ip_networks() _ "!=" _ ip_network:ip_network() { Expression::condition(ip_networks, move |value| *value != ip_network) }
+    / ip_networks:ip_networks() _ ">" _ ip_network:ip_network() { Expression::condition(ip_networks, move |value| *value > ip_network) }
+    / ip_networks:ip_networks() _ ">=" _ ip_network:ip_network() { Expression::condition(ip_networks, move |value| *value >= ip_network) }
+    / ip_networks:ip_networks() _ "<" _ ip_network:ip_network() { Expression::condition(ip_networks, move |value| *value < ip_network) }
+    / ip_networks:ip_networks() _ "<=" _ ip_network:ip_network() { Expression::condition(ip_networks, move |value| *value <= ip_network) }
+
+    rule ipv4_network_condition() -> Expression
+    = ipv4_networks:ipv4_networks() _ "==" _ ipv4_network:ipv4_network() { Expression::condition(ipv4_networks, move |value| *value == ipv4_network) }
+    / ipv4_networks:ipv4_networks() _ "!=" _ ipv4_network:ipv4_network() { Expression::condition(ipv4_networks, move |value| *value != ipv4_network) }
+    / ipv4_networks:ipv4_networks() _ ">" _ ipv4_network:ipv4_network() { Expression::condition(ipv4_networks, move |value| *value > ipv4_network) }
+    / ipv4_networks:ipv4_networks() _ ">=" _ ipv4_network:ipv4_network() { Expression::condition(ipv4_networks, move |value| *value >= ipv4_network) }
+    / ipv4_networks:ipv4_networks() _ "<" _ ipv4_network:ipv4_network() { Expression::condition(ipv4_networks, move |value| *value < ipv4_network) }
+    / ipv4_networks:ipv4_networks() _ "<=" _ ipv4_network:ipv4_network() { Expression::condition(ipv4_networks, move |value| *value <= ipv4_network) }
+
+    rule ipv6_network_condition() -> Expression
+    = ipv6_networks:ipv6_networks() _ "==" _ ipv6_network:ipv6_network() { Expression::condition(ipv6_networks, move |value| *value == ipv6_network) }
+    / ipv6_networks:ipv6_networks() _ "!=" _ ipv6_network:ipv6_network() { Expression::condition(ipv6_networks, move |value| *value != ipv6_network) }
+    / ipv6_networks:ipv6_networks() _ ">" _ ipv6_network:ipv6_network() { Expression::condition(ipv6_networks, move |value| *value > ipv6_network) }
+    / ipv6_networks:ipv6_networks() _ ">=" _ ipv6_network:ipv6_network() { Expression::condition(ipv6_networks, move |value| *value >= ipv6_network) }
+    / ipv6_networks:ipv6_networks() _ "<" _ ipv6_network:ipv6_network() { Expression::condition(ipv6_networks, move |value| *value < ipv6_network) }
+    / ipv6_networks:ipv6_networks() _ "<=" _ ipv6_network:ipv6_network() { Expression::condition(ipv6_networks, move |value| *value <= ipv6_network) }
+
+    rule semantic_version_condition() -> Expression
+    = semantic_versions:semantic_versions() _ "==" _ semantic_version:semantic_version() { Expression::condition(semantic_versions, move |value| *value == semantic_version) }
+    / semantic_versions:semantic_versions() _ "!=" _ semantic_version:semantic_version() { Expression::condition(semantic_versions, move |value| *value != semantic_version) }
+    / semantic_versions:semantic_versions() _ ">" _ semantic_version:semantic_version() { Expression::condition(semantic_versions, move |value| *value > semantic_version) }
+    / semantic_versions:semantic_versions() _ ">=" _ semantic_version:semantic_version() { Expression::condition(semantic_versions, move |value| *value >= semantic_version) }
+    / semantic_versions:semantic_versions() _ "<" _ semantic_version:semantic_version() { Expression::condition(semantic_versions, move |value| *value < semantic_version) }
+    / semantic_versions:semantic_versions() _ "<=" _ semantic_version:semantic_version() { Expression::condition(semantic_versions, move |value| *value <= semantic_version) }
+    / semantic_versions:semantic_versions() __ "matches" boundary() _ semantic_version_requirement:semantic_version_requirement() { Expression::condition(semantic_versions, move |value| semantic_version_requirement.matches(value)) }
 
     // functions
-    rule ip_socket_address_ports(tokens: &Vec<Token>) -> Vec<Term<u16>>
-    = "port(" ip_socket_addresses:ip_socket_addresses(tokens) ")" {
-        ip_socket_addresses
-            .into_iter()
-            .map(|ip_socket_address| Term {
-                position: ip_socket_address.position,
-                value: ip_socket_address.value.port(),
-            })
-            .collect()
-    }
-
-    rule ipv4_socket_address_ports(tokens: &Vec<Token>) -> Vec<Term<u16>>
-    = "port(" ipv4_socket_addresses:ipv4_socket_addresses(tokens) ")" {
-        ipv4_socket_addresses
-            .into_iter()
-            .map(|ipv4_socket_address| Term {
-                position: ipv4_socket_address.position,
-                value: ipv4_socket_address.value.port(),
-            })
-            .collect()
-    }
-
-    rule ipv6_socket_address_ports(tokens: &Vec<Token>) -> Vec<Term<u16>>
-    = "port(" ipv6_socket_addresses:ipv6_socket_addresses(tokens) ")" {
-        ipv6_socket_addresses
-            .into_iter()
-            .map(|ipv6_socket_address| Term {
-                position: ipv6_socket_address.position,
-                value: ipv6_socket_address.value.port(),
-            })
-            .collect()
-    }
-
-    rule ip_socket_address_ips(tokens: &Vec<Token>) -> Vec<Term<IpAddr>>
-    = "ip(" ip_socket_addresses:ip_socket_addresses(tokens) ")" {
-        ip_socket_addresses
-            .into_iter()
-            .map(|ip_socket_address| Term {
-                position: ip_socket_address.position,
-                value: ip_socket_address.value.ip(),
-            })
-            .collect()
-    }
-
-    rule ipv4_socket_address_ips(tokens: &Vec<Token>) -> Vec<Term<Ipv4Addr>>
-    = "ip(" ipv4_socket_addresses:ipv4_socket_addresses(tokens) ")" {
-        ipv4_socket_addresses
-            .into_iter()
-            .map(|ipv4_socket_address| Term {
-                position: ipv4_socket_address.position,
-                value: ipv4_socket_address.value.ip().clone(),
-            })
-            .collect()
-    }
-
-    rule ipv6_socket_address_ips(tokens: &Vec<Token>) -> Vec<Term<Ipv6Addr>>
-    = "ip(" ipv6_socket_addresses:ipv6_socket_addresses(tokens) ")" {
-        ipv6_socket_addresses
-            .into_iter()
-            .map(|ipv6_socket_address| Term {
-                position: ipv6_socket_address.position,
-                value: ipv6_socket_address.value.ip().clone(),
-            })
-            .collect()
-    }
+    rule ip_socket_address_ports() -> Variable<u16>
+    = "port" _ "(" _ ip_socket_addresses:ip_socket_addresses() _ ")" { ip_socket_addresses.map(|ip_socket_address| ip_socket_address.port()) }
+
+    rule ipv4_socket_address_ports() -> Variable<u16>
+    = "port" _ "(" _ ipv4_socket_addresses:ipv4_socket_addresses() _ ")" { ipv4_socket_addresses.map(|ipv4_socket_address| ipv4_socket_address.port()) }
+
+    rule ipv6_socket_address_ports() -> Variable<u16>
+    = "port" _ "(" _ ipv6_socket_addresses:ipv6_socket_addresses() _ ")" { ipv6_socket_addresses.map(|ipv6_socket_address| ipv6_socket_address.port()) }
+
+    rule ip_socket_address_ips() -> Variable<IpAddr>
+    = "ip" _ "(" _ ip_socket_addresses:ip_socket_addresses() _ ")" { ip_socket_addresses.map(|ip_socket_address| ip_socket_address.ip()) }
+
+    rule ipv4_socket_address_ips() -> Variable<Ipv4Addr>
+    = "ip" _ "(" _ ipv4_socket_addresses:ipv4_socket_addresses() _ ")" { ipv4_socket_addresses.map(|ipv4_socket_address| *ipv4_socket_address.ip()) }
+
+    rule ipv6_socket_address_ips() -> Variable<Ipv6Addr>
+    = "ip" _ "(" _ ipv6_socket_addresses:ipv6_socket_addresses() _ ")" { ipv6_socket_addresses.map(|ipv6_socket_address| *ipv6_socket_address.ip()) }
 
     //
     // terms
     //
-    rule integers(tokens: &Vec<Token>) -> Vec<Term<i64>>
-        = "$integer" { Parser::<i64, ()>::from_tokens(tokens, &()) }
+    rule integers() -> Variable<i64>
+        = "$integer" selector:selector()? { select(Variable::new("$integer", Box::new(|tokens: &[Token]| Parser::<i64, ()>::from_tokens(tokens, &()))), selector) }
+
+    rule floats() -> Variable<f64>
+        = "$float" selector:selector()? { select(Variable::new("$float", Box::new(|tokens: &[Token]| Parser::<f64, ()>::from_tokens(tokens, &()))), selector) }
+
+    rule ids() -> Variable<Id>
+        = name:id_name() selector:selector()? { select(Variable::new(name, Box::new(|tokens: &[Token]| Parser::<Id, ()>::from_tokens(tokens, &()))), selector) }
+        / fields()
+
+    rule fields() -> Variable<Id>
+        = "$" field:index() { select(Variable::new("$id", Box::new(|tokens: &[Token]| Parser::<Id, ()>::from_tokens(tokens, &()))), Some(Selector::Field(field))) }
+
+    // `$text` and `$word` are aliases of `$id`, their tokens are typed as text
+    rule id_name() -> &'static str
+        = "$id" { "$id" }
+        / ("$text" / "$word") { "$text" }
+
+    rule dates(formats: &Formats) -> Variable<NaiveDate>
+        = "$date" selector:selector()? {
+            let formats = formats.date.clone();
+            let literals = literals(&formats);
+            select(Variable::new("$date", Box::new(move |tokens: &[Token]| Parser::<NaiveDate, Vec<String>>::from_joined_tokens(tokens, &formats, &literals))), selector)
+        }
+
+    rule times(formats: &Formats) -> Variable<NaiveTime>
+        = "$time" selector:selector()? {
+            let formats = formats.time.clone();
+            let literals = literals(&formats);
+            select(Variable::new("$time", Box::new(move |tokens: &[Token]| Parser::<NaiveTime, Vec<String>>::from_joined_tokens(tokens, &formats, &literals))), selector)
+        }
 
-    rule floats(tokens: &Vec<Token>) -> Vec<Term<f64>>
-        = "$float" { Parser::<f64, ()>::from_tokens(tokens, &()) }
+    rule date_times(formats: &Formats) -> Variable<DateTime<FixedOffset>>
+        = "$dateTime" selector:selector()? {
+            let formats = formats.date_time.clone();
+            let literals = literals(&formats);
+            select(Variable::new("$dateTime", Box::new(move |tokens: &[Token]| Parser::<DateTime<FixedOffset>, Vec<String>>::from_joined_tokens(tokens, &formats, &literals))), selector)
+        }
 
-    rule ids(tokens: &Vec<Token>) -> Vec<Term<Id>>
-        = "$id" { Parser::<Id, ()>::from_tokens(tokens, &()) }
+    rule local_date_times(formats: &Formats) -> Variable<NaiveDateTime>
+        = "$localDateTime" selector:selector()? {
+            let formats = formats.local_date_time.clone();
+            let literals = literals(&formats);
+            select(Variable::new("$localDateTime", Box::new(move |tokens: &[Token]| Parser::<NaiveDateTime, Vec<String>>::from_joined_tokens(tokens, &formats, &literals))), selector)
+        }
 
-    rule dates(tokens: &Vec<Token>, formats: &Formats) -> Vec<Term<NaiveDate>>
-        = "$date" { Parser::<NaiveDate, String>::from_tokens(tokens, &formats.date) }
+    rule timestamps(formats: &Formats) -> Variable<DateTime<Utc>>
+        = "$timestamp" selector:selector()? {
+            let now = formats.now.to_utc();
+            select(Variable::new("$timestamp", Box::new(move |tokens: &[Token]| Parser::<DateTime<Utc>, DateTime<Utc>>::from_joined_tokens(tokens, &now, TIMESTAMP_LITERALS))), selector)
+        }
 
-    rule times(tokens: &Vec<Token>, formats: &Formats) -> Vec<Term<NaiveTime>>
-        = "$time" { Parser::<NaiveTime, String>::from_tokens(tokens, &formats.time) }
+    rule ip_addresses() -> Variable<IpAddr>
+        = "$ipAddress" selector:selector()? { select(Variable::new("$ipAddress", Box::new(|tokens: &[Token]| Parser::<IpAddr, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule date_times(tokens: &Vec<Token>, formats: &Formats) -> Vec<Term<DateTime<FixedOffset>>>
-        = "$dateTime" { Parser::<DateTime<FixedOffset>, String>::from_tokens(tokens, &formats.date_time) }
+    rule ipv4_addresses() -> Variable<Ipv4Addr>
+        = "$ipv4Address" selector:selector()? { select(Variable::new("$ipv4Address", Box::new(|tokens: &[Token]| Parser::<Ipv4Addr, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule local_date_times(tokens: &Vec<Token>, formats: &Formats) -> Vec<Term<NaiveDateTime>>
-        = "$localDateTime" { Parser::<NaiveDateTime, String>::from_tokens(tokens, &formats.local_date_time) }
+    rule ipv6_addresses() -> Variable<Ipv6Addr>
+        = "$ipv6Address" selector:selector()? { select(Variable::new("$ipv6Address", Box::new(|tokens: &[Token]| Parser::<Ipv6Addr, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule ip_addresses(tokens: &Vec<Token>) -> Vec<Term<IpAddr>>
-        = "$ipAddress" { Parser::<IpAddr, ()>::from_tokens(tokens, &()) }
+    rule ip_socket_addresses() -> Variable<SocketAddr>
+        = "$ipSocketAddress" selector:selector()? { select(Variable::new("$ipSocketAddress", Box::new(|tokens: &[Token]| Parser::<SocketAddr, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule ipv4_addresses(tokens: &Vec<Token>) -> Vec<Term<Ipv4Addr>>
-        = "$ipv4Address" { Parser::<Ipv4Addr, ()>::from_tokens(tokens, &()) }
+    rule ipv4_socket_addresses() -> Variable<SocketAddrV4>
+        = "$ipv4SocketAddress" selector:selector()? { select(Variable::new("$ipv4SocketAddress", Box::new(|tokens: &[Token]| Parser::<SocketAddrV4, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule ipv6_addresses(tokens: &Vec<Token>) -> Vec<Term<Ipv6Addr>>
-        = "$ipv6Address" { Parser::<Ipv6Addr, ()>::from_tokens(tokens, &()) }
+    rule ipv6_socket_addresses() -> Variable<SocketAddrV6>
+        = "$ipv6SocketAddress" selector:selector()? { select(Variable::new("$ipv6SocketAddress", Box::new(|tokens: &[Token]| Parser::<SocketAddrV6, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule ip_socket_addresses(tokens: &Vec<Token>) -> Vec<Term<SocketAddr>>
-        = "$ipSocketAddress" { Parser::<SocketAddr, ()>::from_tokens(tokens, &()) }
+    rule ip_networks() -> Variable<IpNet>
+        = "$ipNetwork" selector:selector()? { select(Variable::new("$ipNetwork", Box::new(|tokens: &[Token]| Parser::<IpNet, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule ipv4_socket_addresses(tokens: &Vec<Token>) -> Vec<Term<SocketAddrV4>>
-        = "$ipv4SocketAddress" { Parser::<SocketAddrV4, ()>::from_tokens(tokens, &()) }
+    rule ipv4_networks() -> Variable<Ipv4Net>
+        = "$ipv4Network" selector:selector()? { select(Variable::new("$ipv4Network", Box::new(|tokens: &[Token]| Parser::<Ipv4Net, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule ipv6_socket_addresses(tokens: &Vec<Token>) -> Vec<Term<SocketAddrV6>>
-        = "$ipv6SocketAddress" { Parser::<SocketAddrV6, ()>::from_tokens(tokens, &()) }
+    rule ipv6_networks() -> Variable<Ipv6Net>
+        = "$ipv6Network" selector:selector()? { select(Variable::new("$ipv6Network", Box::new(|tokens: &[Token]| Parser::<Ipv6Net, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule ip_networks(tokens: &Vec<Token>) -> Vec<Term<IpNet>>
-        = "$ipNetwork" { Parser::<IpNet, ()>::from_tokens(tokens, &()) }
+    rule semantic_versions() -> Variable<Version>
+        = "$semanticVersion" selector:selector()? { select(Variable::new("$semanticVersion", Box::new(|tokens: &[Token]| Parser::<Version, ()>::from_tokens(tokens, &()))), selector) }
 
-    rule ipv4_networks(tokens: &Vec<Token>) -> Vec<Term<Ipv4Net>>
-        = "$ipv4Network" { Parser::<Ipv4Net, ()>::from_tokens(tokens, &()) }
+    //
+    // whitespace
+    //
+    rule _() = quiet!{[' '|'\t'|'\r'|'\n']*}
 
-    rule ipv6_networks(tokens: &Vec<Token>) -> Vec<Term<Ipv6Net>>
-        = "$ipv6Network" { Parser::<Ipv6Net, ()>::from_tokens(tokens, &()) }
+    rule __() = quiet!{[' '|'\t'|'\r'|'\n']+}
 
-    rule semantic_versions(tokens: &Vec<Token>) -> Vec<Term<Version>>
-        = "$semanticVersion" { Parser::<Version, ()>::from_tokens(tokens, &()) }
+    rule boundary() = !['a'..='z'|'A'..='Z'|'0'..='9'|'_'|'-']
+
+    //
+    // selectors
+    //
+    rule selector() -> Selector
+        = "@" field:index() { Selector::Field(field) }
+        / "[" occurrence:index() "]" { Selector::Occurrence(occurrence) }
+
+    rule index() -> usize
+        = n:$(['0'..='9']+) {?
+            match usize::from_str(n) {
+                Ok(index) if index > 0 => Ok(index),
+                _ => Err("failed to parse index"),
+            }
+        }
 
     //
     // values
@@ -388,6 +527,11 @@ peg::parser!(pub grammar expression() for str {
             f64::from_word(n, &()).map_err(|_| "failed to parse float")
         }
 
+    rule number() -> usize
+        = n:$(['0'..='9']+) {?
+            usize::from_str(n).map_err(|_| "failed to parse number")
+        }
+
     rule port() -> u16
         = n:$(['0'..='9']+) {?
             u16::from_word(n, &()).map_err(|_| "failed to parse port")
@@ -398,26 +542,74 @@ peg::parser!(pub grammar expression() for str {
             Id::from_word(n, &()).map_err(|_| "failed to parse id")
         }
 
+    rule text() -> Id
+        = string()
+        / id()
+
+    // any column value of a field, e.g. a status code `404`, may also start with a digit
+    rule word() -> Id
+        = string()
+        / n:$(['a'..='z'|'A'..='Z'|'0'..='9'] ['a'..='z'|'A'..='Z'|'0'..='9'|'+'|'-'|'.'|':'|'_']*) {?
+            Id::from_word(n, &()).map_err(|_| "failed to parse word")
+        }
+
+    rule string() -> String
+        = "\"" n:$(("\\" [_] / [^'"'|'\\'])*) "\"" {? unescape(n) }
+        / "'" n:$(("\\" [_] / [^'\''|'\\'])*) "'" {? unescape(n) }
+
+    rule regex() -> Regex
+        = "\"" n:$(("\\" [_] / [^'"'|'\\'])*) "\"" {?
+            Regex::new(&n.replace("\\\"", "\"")).map_err(|_| "failed to parse regex")
+        }
+        / "'" n:$(("\\" [_] / [^'\''|'\\'])*) "'" {?
+            Regex::new(&n.replace("\\'", "'")).map_err(|_| "failed to parse regex")
+        }
+
     rule date(formats: &Formats) -> NaiveDate
-        = n:$([^'('|')'|' ']+) {?
-            NaiveDate::from_word(n, &formats.date).map_err(|_| "failed to parse date")
+        = n:formatted() {?
+            match relative(&n, &formats.now) {
+                Some(now) => Ok(now.date_naive()),
+                None => NaiveDate::from_word(&n, &formats.date).map_err(|_| "failed to parse date"),
+            }
         }
 
     rule time(formats: &Formats) -> NaiveTime
-        = n:$([^'('|')'|' ']+) {?
-            NaiveTime::from_word(n, &formats.time).map_err(|_| "failed to parse time")
+        = n:formatted() {?
+            match relative(&n, &formats.now) {
+                Some(now) => Ok(now.time()),
+                None => NaiveTime::from_word(&n, &formats.time).map_err(|_| "failed to parse time"),
+            }
         }
 
     rule date_time(formats: &Formats) -> DateTime<FixedOffset>
-        = n:$([^'('|')'|' ']+) {?
-            DateTime::<FixedOffset>::from_word(n, &formats.date_time).map_err(|_| "failed to parse dateTime")
+        = n:formatted() {?
+            match relative(&n, &formats.now) {
+                Some(now) => Ok(now),
+                None => DateTime::<FixedOffset>::from_word(&n, &formats.date_time).map_err(|_| "failed to parse dateTime"),
+            }
         }
 
     rule local_date_time(formats: &Formats) -> NaiveDateTime
-        = n:$([^'('|')'|' ']+) {?
-            NaiveDateTime::from_word(n, &formats.local_date_time).map_err(|_| "failed to parse localDateTime")
+        = n:formatted() {?
+            match relative(&n, &formats.now) {
+                Some(now) => Ok(now.naive_local()),
+                None => NaiveDateTime::from_word(&n, &formats.local_date_time).map_err(|_| "failed to parse localDateTime"),
+            }
+        }
+
+    rule timestamp(formats: &Formats) -> DateTime<Utc>
+        = n:formatted() {?
+            match relative(&n, &formats.now) {
+                Some(now) => Ok(now.to_utc()),
+                None => DateTime::<Utc>::from_word(&n, &formats.now.to_utc()).map_err(|_| "failed to parse timestamp"),
+            }
         }
 
+    // values formatted with a format string containing spaces are quoted
+    rule formatted() -> String
+        = string()
+        / n:$([^'('|')'|' '|'\t'|'\r'|'\n']+) { n.to_string() }
+
     rule ip_address() -> IpAddr
         = n:$(['0'..='9'|'a'..='f'|'A'..='F'|'.'|':']+) {?
             IpAddr::from_word(n, &()).map_err(|_| "failed to parse IP address")
@@ -474,33 +666,53 @@ peg::parser!(pub grammar expression() for str {
         }
 });
 
-fn matches<T, P>(terms: &Vec<Term<T>>, predicate: P) -> HashSet<Position>
-where
-    P: FnMut(&&Term<T>) -> bool,
-{
-    terms
-        .into_iter()
-        .filter(predicate)
-        .map(|term| term.position)
-        .collect::<HashSet<Position>>()
+fn unescape(literal: &str) -> Result<String, &'static str> {
+    let mut result = String::new();
+    let mut characters = literal.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            result.push(character);
+            continue;
+        }
+
+        match characters.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('0') => result.push('\0'),
+            Some('\\') => result.push('\\'),
+            Some('"') => result.push('"'),
+            Some('\'') => result.push('\''),
+            _ => return Err("failed to parse string"),
+        }
+    }
+
+    Ok(result)
 }
 
 pub struct Validator {}
 
 impl Validator {
     pub fn validate_formats(formats: &Formats) -> Result<(), Error> {
-        Validator::validate_format("$date", &formats.date)?;
-        Validator::validate_format("$time", &formats.time)?;
-        Validator::validate_format("$dateTime", &formats.date_time)?;
-        Validator::validate_format("$localDateTime", &formats.local_date_time)?;
+        let classes = [
+            ("$date", &formats.date),
+            ("$time", &formats.time),
+            ("$dateTime", &formats.date_time),
+            ("$localDateTime", &formats.local_date_time),
+        ];
+        for (class, formats) in classes {
+            for format in formats {
+                Validator::validate_format(class, format)?;
+            }
+        }
 
         Ok(())
     }
 
     fn validate_format(class: &str, format: &str) -> Result<(), Error> {
-        if format.chars().any(|c| c == ' ' || c == '(' || c == ')') {
+        if format.chars().any(|c| c == '(' || c == ')') {
             return Err(anyhow!(
-                "'{}' format string '{}' must not contain grammar delimiters ' ' or '(' or ')'",
+                "'{}' format string '{}' must not contain grammar delimiters '(' or ')'",
                 class,
                 format
             ));
@@ -518,13 +730,10 @@ impl Validator {
     }
 
     pub fn validate_separators(expression: &str, separators: &Separators, formats: &Formats) -> Result<(), Error> {
+        let expression = &Validator::strip_literals(expression);
         Validator::validate_class_separators(expression, "$integer", separators, "+-")?;
         Validator::validate_class_separators(expression, "$float", separators, "+-.")?;
         Validator::validate_class_separators(expression, "$id", separators, "+-.:_")?;
-        Validator::validate_class_separators(expression, "$date", separators, "/-.:+")?;
-        Validator::validate_class_separators(expression, "$time", separators, "/-.:+")?;
-        Validator::validate_class_separators(expression, "$dateTime", separators, "/-.:+")?;
-        Validator::validate_class_separators(expression, "$localDateTime", separators, "/-.:+")?;
         Validator::validate_class_separators(expression, "$ipAddress", separators, ".:")?;
         Validator::validate_class_separators(expression, "$ipv4Address", separators, ".")?;
         Validator::validate_class_separators(expression, "$ipv6Address", separators, ":")?;
@@ -536,14 +745,40 @@ impl Validator {
         Validator::validate_class_separators(expression, "$ipv6Network", separators, ":/")?;
         Validator::validate_class_separators(expression, "$semanticVersion", separators, ".-+")?;
 
-        Validator::validate_format_separators(expression, "$date", separators, &formats.date)?;
-        Validator::validate_format_separators(expression, "$time", separators, &formats.time)?;
-        Validator::validate_format_separators(expression, "$dateTime", separators, &formats.date_time)?;
-        Validator::validate_format_separators(expression, "$localDateTime", separators, &formats.local_date_time)?;
+        let classes = [
+            ("$date", &formats.date),
+            ("$time", &formats.time),
+            ("$dateTime", &formats.date_time),
+            ("$localDateTime", &formats.local_date_time),
+        ];
+        for (class, formats) in classes {
+            for format in formats {
+                Validator::validate_format_separators(expression, class, separators, format)?;
+            }
+        }
 
         Ok(())
     }
 
+    fn strip_literals(expression: &str) -> String {
+        let mut result = String::new();
+        let mut quote = None;
+        let mut characters = expression.chars();
+        while let Some(character) = characters.next() {
+            match quote {
+                None if character == '"' || character == '\'' => quote = Some(character),
+                None => result.push(character),
+                Some(_) if character == '\\' => {
+                    characters.next();
+                }
+                Some(delimiter) if character == delimiter => quote = None,
+                Some(_) => {}
+            }
+        }
+
+        return result;
+    }
+
     fn validate_class_separators(
         expression: &str,
         class: &str,
@@ -570,12 +805,17 @@ impl Validator {
         separators: &Separators,
         format: &str,
     ) -> Result<(), Error> {
+        // values spanning multiple tokens must start and end with a token which is not a separator
         if expression.contains(class) {
-            let raw_format = format.replace("%", "");
-            let separator_characters = separators.comprise_any(&raw_format);
+            let items: Vec<Item> = StrftimeItems::new(format).collect();
+            let literal = |item: Option<&Item>| match item {
+                Some(Item::Literal(literal)) | Some(Item::Space(literal)) => literal.to_string(),
+                _ => String::new(),
+            };
+            let separator_characters = separators.comprise_any(&(literal(items.first()) + &literal(items.last())));
             if separator_characters.chars().count() != 0 {
                 return Err(anyhow!(
-                    "separator(s) '{}' can not be used in '{}' format string '{}' with an expression containing '{}'",
+                    "separator(s) '{}' can not be used at the start or end of '{}' format string '{}' with an expression containing '{}'",
                     separator_characters,
                     class,
                     format,
@@ -588,37 +828,6 @@ impl Validator {
     }
 }
 
-#[cfg(test)]
-mod matches_tests {
-    use super::*;
-
-    #[test]
-    fn integer_matches() {
-        // setup
-        let integers = vec![
-            Term { position: 2, value: 1 },
-            Term { position: 4, value: 2 },
-            Term { position: 6, value: 3 },
-        ];
-
-        // exercise
-        let integers_eq_integer_0 = matches(&integers, |term| term.value == 0);
-        let integers_eq_integer_2 = matches(&integers, |term| term.value == 2);
-        let integeres_ne_integer_0 = matches(&integers, |term| term.value != 0);
-        let integers_ne_integer_2 = matches(&integers, |term| term.value != 2);
-        let integers_gt_integer_0 = matches(&integers, |term| term.value > 0);
-        let integers_lt_integer_0 = matches(&integers, |term| term.value < 0);
-
-        // verify
-        assert_eq!(HashSet::from([]), integers_eq_integer_0);
-        assert_eq!(HashSet::from([4]), integers_eq_integer_2);
-        assert_eq!(HashSet::from([2, 4, 6]), integeres_ne_integer_0);
-        assert_eq!(HashSet::from([2, 6]), integers_ne_integer_2);
-        assert_eq!(HashSet::from([2, 4, 6]), integers_gt_integer_0);
-        assert_eq!(HashSet::from([]), integers_lt_integer_0);
-    }
-}
-
 #[cfg(test)]
 mod validator_tests {
     use super::*;
@@ -628,21 +837,28 @@ mod validator_tests {
     fn validate_formats() {
         // setup
         let valid_formats = test_utils::default_formats();
+        let valid_multiple_formats = Formats {
+            date: vec![String::from("%F"), String::from("%d/%m/%Y")],
+            ..test_utils::default_formats()
+        };
         let invalid_formats_chrono_specifier = Formats {
-            date: String::from("%F"),
-            time: String::from("%T"),
-            date_time: String::from("%+"),
-            local_date_time: String::from("%c"),
+            date: vec![String::from("%F")],
+            time: vec![String::from("%T")],
+            date_time: vec![String::from("%+")],
+            local_date_time: vec![String::from("%c")],
+            ..test_utils::default_formats()
         };
         let invalid_formats_grammar_delimiter = Formats {
-            date: String::from("(%F)"),
-            time: String::from("%T"),
-            date_time: String::from("%+"),
-            local_date_time: String::from("%Y-%m-%dT%H:%M:%S%.f"),
+            date: vec![String::from("%F"), String::from("(%F)")],
+            time: vec![String::from("%T")],
+            date_time: vec![String::from("%+")],
+            local_date_time: vec![String::from("%Y-%m-%dT%H:%M:%S%.f")],
+            ..test_utils::default_formats()
         };
 
         // exercise & verify
         assert!(Validator::validate_formats(&valid_formats).is_ok());
+        assert!(Validator::validate_formats(&valid_multiple_formats).is_ok());
         assert!(Validator::validate_formats(&invalid_formats_chrono_specifier).is_err());
         assert!(Validator::validate_formats(&invalid_formats_grammar_delimiter).is_err());
     }
@@ -656,6 +872,32 @@ mod validator_tests {
         // exercise & verify
         assert!(Validator::validate_separators("$integer == 5", &separators, &formats).is_ok());
         assert!(Validator::validate_separators("$id == a", &separators, &formats).is_err());
+        assert!(Validator::validate_separators(r#"$integer == 5 and $text == "$id""#, &separators, &formats).is_ok());
+        assert!(Validator::validate_separators(r#"$text =~ '\'$id' and $id == a"#, &separators, &formats).is_err());
+        assert!(Validator::validate_separators("$time == 21:41:07", &separators, &formats).is_ok());
+        assert!(Validator::validate_separators(
+            "$time == 21:41:07",
+            &separators,
+            &Formats {
+                time: vec![String::from("%T"), String::from("%T:")],
+                ..test_utils::default_formats()
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn strip_literals() {
+        assert_eq!(Validator::strip_literals("$id == a"), "$id == a");
+        assert_eq!(Validator::strip_literals(r#"$id == "$date""#), "$id == ");
+        assert_eq!(
+            Validator::strip_literals(r#"$id == '$date' and $time == 1"#),
+            "$id ==  and $time == 1"
+        );
+        assert_eq!(
+            Validator::strip_literals(r#"$id == "a\"$date" or $float < 1"#),
+            "$id ==  or $float < 1"
+        );
     }
 }
 
@@ -702,6 +944,126 @@ mod expression_tests {
         assert_valid_expression("$date < 2021-01-01");
         assert_valid_expression("$date <= 2021-01-01");
         assert_valid_expression("($date == 2021-01-01)");
+        assert_valid_expression(r#"$date == "2021-01-01""#);
+    }
+
+    #[test]
+    fn invalid_timestamp_expressions() {
+        assert_invalid_expression("$timestamp == 2021-01-01");
+        assert_invalid_expression("$timestamp contains 1634420467");
+    }
+
+    #[test]
+    fn valid_relative_expressions() {
+        assert_valid_expression("$date == today");
+        assert_valid_expression("$date >= yesterday");
+        assert_valid_expression("$time > -15m");
+        assert_valid_expression("$dateTime > now-1h");
+        assert_valid_expression("$localDateTime < now");
+        assert_valid_expression("$timestamp >= today-1w");
+        assert_valid_expression(r#"$dateTime > "now-1h""#);
+    }
+
+    #[test]
+    fn invalid_relative_expressions() {
+        assert_invalid_expression("$date == tomorrow");
+        assert_invalid_expression("$time > -15");
+        assert_invalid_expression("$dateTime > now-1y");
+    }
+
+    #[test]
+    fn valid_timestamp_expressions() {
+        assert_valid_expression("$timestamp == 2021-10-16T21:41:07Z");
+        assert_valid_expression("$timestamp != 1634420467");
+        assert_valid_expression("$timestamp > 1634420467000");
+        assert_valid_expression("$timestamp >= 2021-W41-6");
+        assert_valid_expression(r#"$timestamp < "Sat, 16 Oct 2021 21:41:07 +0000""#);
+        assert_valid_expression(r#"$timestamp <= "[16/Oct/2021:21:41:07 +0000]""#);
+        assert_valid_expression("($timestamp == 2021-10-16T21:41:07Z)");
+    }
+
+    #[test]
+    fn valid_regex_expressions() {
+        assert_valid_expression(r#"$id =~ "^ERR[0-9]+$""#);
+        assert_valid_expression(r#"$id !~ "^ERR[0-9]+$""#);
+        assert_valid_expression(r#"$id matches-regex "^ERR[0-9]+$""#);
+        assert_valid_expression(r#"$text =~ "^/var/log/.*\.log$""#);
+        assert_valid_expression(r#"$word !~ "\d+""#);
+        assert_valid_expression(r#"$text =~ "say \"hello\"""#);
+        assert_valid_expression(r#"$text =~ "\\""#);
+        assert_valid_expression(r#"$text =~ """#);
+        assert_valid_expression(r#"$3 =~ "^ERR" and $integer > 500"#);
+        assert_valid_expression(r#"$id =~ "^ERR[0-9]+$" and $integer > 500"#);
+    }
+
+    #[test]
+    fn invalid_regex_expressions() {
+        assert_invalid_expression(r#"$id =~ ^ERR"#);
+        assert_invalid_expression(r#"$id =~ "^ERR"#);
+        assert_invalid_expression(r#"$id =~ "ERR[""#);
+        assert_invalid_expression(r#"$id =~ "\""#);
+        assert_invalid_expression(r#"$integer =~ "^1""#);
+        assert_invalid_expression(r#"$text matches "^1""#);
+    }
+
+    #[test]
+    fn valid_text_expressions() {
+        assert_valid_expression("$text == qpanda");
+        assert_valid_expression("$word != qpanda");
+        assert_valid_expression("$text contains pan");
+        assert_valid_expression("$text starts-with qp");
+        assert_valid_expression("$text ends-with da");
+        assert_valid_expression("$text@2 == qpanda");
+        assert_valid_expression("$word[1] == qpanda");
+    }
+
+    #[test]
+    fn valid_string_expressions() {
+        assert_valid_expression(r#"$id == "404-not-found""#);
+        assert_valid_expression(r#"$text == 'user@host'"#);
+        assert_valid_expression(r#"$text == "/var/log""#);
+        assert_valid_expression(r#"$text == "a == b and c""#);
+        assert_valid_expression(r#"$text == "" or $text == ''"#);
+        assert_valid_expression(r#"$text contains "say \"hi\"""#);
+        assert_valid_expression(r#"$text starts-with 'it\'s'"#);
+        assert_valid_expression(r#"$text ends-with "\t\\""#);
+        assert_valid_expression(r#"$2 == "1""#);
+    }
+
+    #[test]
+    fn invalid_string_expressions() {
+        assert_invalid_expression(r#"$text == "unterminated"#);
+        assert_invalid_expression(r#"$text == 'unterminated"#);
+        assert_invalid_expression(r#"$text == "\q""#);
+        assert_invalid_expression(r#"$integer == "1""#);
+    }
+
+    #[test]
+    fn valid_field_expressions() {
+        assert_valid_expression("$1 == tcp");
+        assert_valid_expression("$12 contains ESTABLISHED");
+        assert_valid_expression("$integer@2 > 100");
+        assert_valid_expression("$id@5 starts-with ERR");
+        assert_valid_expression("$date@1 > 2021-01-01");
+        assert_valid_expression("$integer[2] == 0");
+        assert_valid_expression("port($ipv4SocketAddress@4) == 22");
+        assert_valid_expression("ip($ipSocketAddress[1]) in 10.0.0.0/8");
+        assert_valid_expression("$1 == tcp and $integer@2 > 100");
+        assert_valid_expression("$3 == 404");
+        assert_valid_expression("$2 starts-with 5 and $4 != 1.0-rc1");
+    }
+
+    #[test]
+    fn invalid_field_expressions() {
+        assert_invalid_expression("$0 == tcp");
+        assert_invalid_expression("$integer@0 > 100");
+        assert_invalid_expression("$integer[0] > 100");
+        assert_invalid_expression("$integer@ > 100");
+        assert_invalid_expression("$integer[] > 100");
+        assert_invalid_expression("$integer[2 > 100");
+        assert_invalid_expression("$integer@-1 > 100");
+        assert_invalid_expression("$integer@2[1] > 100");
+        assert_invalid_expression("$1 > 2021-01-01 and $1 in 10.0.0.0/8");
     }
 
     #[test]
@@ -714,6 +1076,8 @@ mod expression_tests {
         assert_valid_expression("$integer > 9 and $float < 5.5");
         assert_valid_expression("($integer > 9) and ($integer > 8)");
         assert_valid_expression("(($integer > 9) and ($integer > 8))");
+        assert_valid_expression("$integer > 9 and $integer > 8 and $integer > 7");
+        assert_valid_expression("$integer > 9 and $integer > 8 and $integer > 7 and $integer > 6");
     }
 
     #[test]
@@ -723,8 +1087,6 @@ mod expression_tests {
         assert_invalid_expression("$integer > 9 (and < 5.5)");
         assert_invalid_expression("($integer > 9)($integer > 8)");
         assert_invalid_expression("(($integer > 9)($integer > 8))");
-        assert_invalid_expression("$integer > 9 and $integer > 8 and $integer > 7");
-        assert_invalid_expression("$integer > 9 and $integer > 8 and $integer > 7 and $integer > 6");
         assert_invalid_expression("$integer > 9 and $integer > 8) and ($integer > 7 and $integer > 6");
     }
 
@@ -738,6 +1100,8 @@ mod expression_tests {
         assert_valid_expression("$integer > 9 or $float < 5.5");
         assert_valid_expression("($integer > 9) or ($integer > 8)");
         assert_valid_expression("(($integer > 9) or ($integer > 8))");
+        assert_valid_expression("$integer > 9 or $integer > 8 or $integer > 7");
+        assert_valid_expression("$integer > 9 or $integer > 8 or $integer > 7 or $integer > 6");
     }
 
     #[test]
@@ -748,8 +1112,6 @@ mod expression_tests {
         assert_invalid_expression("$integer > 9 (or < 5.5)");
         assert_invalid_expression("($integer > 9)($integer > 8)");
         assert_invalid_expression("(($integer > 9)($integer > 8))");
-        assert_invalid_expression("$integer > 9 or $integer > 8 or $integer > 7");
-        assert_invalid_expression("$integer > 9 or $integer > 8 or $integer > 7 or $integer > 6");
         assert_invalid_expression("$integer > 9 or $integer > 8) or ($integer > 7 or $integer > 6");
     }
 
@@ -773,53 +1135,182 @@ mod expression_tests {
     fn valid_and_and_expressions() {
         assert_valid_expression("$integer > 9 and ($integer > 8 and $float < 5.5)");
         assert_valid_expression("($integer > 9 and $integer > 8) and $float < 5.5");
-    }
-
-    #[test]
-    fn invalid_and_and_expressions() {
-        assert_invalid_expression("$integer > 9 and $integer > 8 and $float < 5.5");
-        assert_invalid_expression("($integer > 9) and ($integer > 8) and ($float < 5.5)");
+        assert_valid_expression("$integer > 9 and $integer > 8 and $float < 5.5");
+        assert_valid_expression("($integer > 9) and ($integer > 8) and ($float < 5.5)");
     }
 
     #[test]
     fn valid_or_or_expressions() {
         assert_valid_expression("$integer > 9 or ($integer > 8 or $float < 5.5)");
         assert_valid_expression("($integer > 9 or $integer > 8) or $float < 5.5");
+        assert_valid_expression("$integer > 9 or $integer > 8 or $float < 5.5");
+        assert_valid_expression("($integer > 9) or ($integer > 8) or ($float < 5.5)");
+    }
+
+    #[test]
+    fn valid_not_expressions() {
+        assert_valid_expression("not $integer > 9");
+        assert_valid_expression("!$integer > 9");
+        assert_valid_expression("! $integer > 9");
+        assert_valid_expression("not($integer > 9)");
+        assert_valid_expression("not ($integer > 9)");
+        assert_valid_expression("!($integer > 9)");
+        assert_valid_expression("not not $integer > 9");
+        assert_valid_expression("not ($id == root) and $integer > 5 and $float < 1.0");
+        assert_valid_expression("not ($integer > 9 or ($float < 5.5 and not $id == root))");
+        assert_valid_expression("$ipAddress not in 10.0.0.0/8 and not $ipAddress in 10.0.0.0/8");
+    }
+
+    #[test]
+    fn invalid_not_expressions() {
+        assert_invalid_expression("not");
+        assert_invalid_expression("not()");
+        assert_invalid_expression("$integer > 9 not");
+        assert_invalid_expression("$integer > 9 and not");
+        assert_invalid_expression("nota $integer > 9");
+    }
+
+    #[test]
+    fn valid_correlation_expressions() {
+        assert_valid_expression("$integer > 1 followed-by $integer > 2");
+        assert_valid_expression("$ipv4Address in 10.0.0.0/8 followed-by $integer == 22");
+        assert_valid_expression("$date == 2021-01-01 within 3 $id == ERROR");
+        assert_valid_expression("$integer > 20 same-token $integer < 30");
+        assert_valid_expression("$integer > 1 followed-by $integer > 2 followed-by $integer > 3");
+        assert_valid_expression("($integer > 1 within 2 $float < 1.0) or $id == a");
+        assert_valid_expression("$id == a and $integer > 1 followed-by ($integer > 2 or $float < 1.0)");
+        assert_valid_expression("not $integer > 1 followed-by $integer > 2");
+    }
+
+    #[test]
+    fn invalid_correlation_expressions() {
+        assert_invalid_expression("$integer > 1 followed-by");
+        assert_invalid_expression("followed-by $integer > 1");
+        assert_invalid_expression("$integer > 1 followedby $integer > 2");
+        assert_invalid_expression("$integer > 1 within $integer > 2");
+        assert_invalid_expression("$integer > 1 within 0 $integer > 2");
+        assert_invalid_expression("$integer > 1 within3 $integer > 2");
+        assert_invalid_expression("$integer > 1 same-token-x $integer > 2");
+    }
+
+    #[test]
+    fn valid_quantifier_expressions() {
+        assert_valid_expression("all($integer < 100)");
+        assert_valid_expression("none($ipv6Address)");
+        assert_valid_expression("none($id == root)");
+        assert_valid_expression("none($dateTime)");
+        assert_valid_expression("count($ipSocketAddress) >= 3");
+        assert_valid_expression("count($integer > 5)==0");
+        assert_valid_expression("count ( $date ) < 2");
+        assert_valid_expression("count($integer[1]) != 1");
+        assert_valid_expression("all($integer@2 > 1) and none($float)");
+        assert_valid_expression("not all($integer < 100) or count(port($ipv4SocketAddress) == 22) > 1");
     }
 
     #[test]
-    fn invalid_or_or_expressions() {
-        assert_invalid_expression("$integer > 9 or $integer > 8 or $float < 5.5");
-        assert_invalid_expression("($integer > 9) or ($integer > 8) or ($float < 5.5)");
+    fn invalid_quantifier_expressions() {
+        assert_invalid_expression("all($integer)");
+        assert_invalid_expression("all()");
+        assert_invalid_expression("none()");
+        assert_invalid_expression("count($integer)");
+        assert_invalid_expression("count($integer) >= -1");
+        assert_invalid_expression("count($integer) contains 1");
+        assert_invalid_expression("none($integer) > 1");
+        assert_invalid_expression("none($dateTimes)");
+        assert_invalid_expression("all $integer < 100");
+    }
+
+    #[test]
+    fn valid_whitespace_expressions() {
+        assert_valid_expression("$integer>9");
+        assert_valid_expression("$integer  > 9");
+        assert_valid_expression("$integer\t>=\t9");
+        assert_valid_expression(" $integer > 9 ");
+        assert_valid_expression("( $integer > 9 )");
+        assert_valid_expression("($integer>9)and($float<5.5)");
+        assert_valid_expression("$integer > 9  or\n$float < 5.5");
+        assert_valid_expression("$id==a and $date<=2021-01-01");
+        assert_valid_expression("$text!='a b'");
+        assert_valid_expression(r#"$id=~"^ERR""#);
+        assert_valid_expression("$text  contains  pan");
+        assert_valid_expression("$ipAddress  not  in  10.0.0.0/8");
+        assert_valid_expression("port ( $ipv4SocketAddress ) == 22");
+        assert_valid_expression("$integer == 9 and$integer == 1");
+        assert_valid_expression("$integer == 9 or$float < 5.5 and not$id == a");
+        assert_valid_expression("not$integer > 9");
+    }
+
+    #[test]
+    fn invalid_whitespace_expressions() {
+        assert_invalid_expression("$integer > 9 andnot $float < 5.5");
+        assert_invalid_expression("$integer > 9 and-$float < 5.5");
+        assert_invalid_expression("$text containspan");
+        assert_invalid_expression("$textcontains pan");
+        assert_invalid_expression("$ipAddress notin 10.0.0.0/8");
+        assert_invalid_expression("$integer > 9 an d $float < 5.5");
+        assert_invalid_expression("$integer @2 > 9");
+        assert_invalid_expression("$ integer > 9");
+    }
+
+    #[test]
+    fn invalid_projections() {
+        assert_invalid_projection("");
+        assert_invalid_projection("$integer,");
+        assert_invalid_projection("$integer == 9");
+        assert_invalid_projection("$dateTimes");
+        assert_invalid_projection("port($ipv4Address)");
+    }
+
+    #[test]
+    fn valid_projections() {
+        assert_valid_projection("$integer");
+        assert_valid_projection("$date,$dateTime");
+        assert_valid_projection(" $id@1 , $integer[2] ");
+        assert_valid_projection("$3,port($ipSocketAddress),ip( $ipv4SocketAddress )");
+    }
+
+    fn assert_valid_projection(projection: &str) {
+        assert!(expression::projection(projection, &test_utils::default_formats()).is_ok());
+    }
+
+    fn assert_invalid_projection(projection: &str) {
+        assert!(expression::projection(projection, &test_utils::default_formats()).is_err());
     }
 
     fn assert_valid_expression(expression: &str) {
-        assert!(expression::evaluate(expression, &vec![], &test_utils::default_formats()).is_ok());
+        assert!(expression::compile(expression, &test_utils::default_formats()).is_ok());
     }
 
     fn assert_invalid_expression(expression: &str) {
-        assert!(expression::evaluate(expression, &vec![], &test_utils::default_formats()).is_err());
+        assert!(expression::compile(expression, &test_utils::default_formats()).is_err());
     }
 }
 
 #[cfg(test)]
 mod evaluation_tests {
     use super::*;
+    use crate::evaluator::Evaluate;
+    use crate::evaluator::Types;
     use crate::filter::test_utils;
+    use crate::tokenizer::Position;
+    use crate::tokenizer::Tokenizer;
+    use peg::error::ParseError;
+    use peg::str::LineCol;
+    use std::collections::HashSet;
 
     #[test]
     fn evaluate_expression_without_tokens() {
         assert_eq!(
-            expression::evaluate("$integer == 9", &vec![], &test_utils::default_formats()),
-            Ok(HashSet::new())
+            evaluate("$integer == 9", &vec![], &test_utils::default_formats()),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("$integer != 9", &vec![], &test_utils::default_formats()),
-            Ok(HashSet::new())
+            evaluate("$integer != 9", &vec![], &test_utils::default_formats()),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("$float > 1.0", &vec![], &test_utils::default_formats()),
-            Ok(HashSet::new())
+            evaluate("$float > 1.0", &vec![], &test_utils::default_formats()),
+            Ok(None)
         );
     }
 
@@ -836,13 +1327,10 @@ mod evaluation_tests {
 
         // exercise & verify
         assert_eq!(
-            expression::evaluate("$integer == 9", &tokens, &formats),
-            Ok(HashSet::from([0]))
-        );
-        assert_eq!(
-            expression::evaluate("$integer != 9", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$integer == 9", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
+        assert_eq!(evaluate("$integer != 9", &tokens, &formats), Ok(None));
     }
 
     #[test]
@@ -858,17 +1346,112 @@ mod evaluation_tests {
 
         // exercise & verify
         assert_eq!(
-            expression::evaluate("$float == 5.5", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$float == 5.5", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(evaluate("$float != 5.5", &tokens, &formats), Ok(None));
+    }
+
+    #[test]
+    fn evaluate_id_expression() {
+        // setup
+        let tokens = vec![Token {
+            position: 0,
+            separator: false,
+            word: "qpanda",
+        }];
+
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
+        assert_eq!(
+            evaluate("$id == qpanda", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(
+            evaluate("$id contains and", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(
+            evaluate("$id starts-with qpa", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$float != 5.5", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$id ends-with nda", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
+        assert_eq!(evaluate("$id != qpanda", &tokens, &formats), Ok(None));
     }
 
     #[test]
-    fn evaluate_id_expression() {
+    fn evaluate_regex_expression() {
+        // setup
+        let tokens = vec![
+            Token {
+                position: 0,
+                separator: false,
+                word: "ERR42",
+            },
+            Token {
+                position: 1,
+                separator: true,
+                word: " ",
+            },
+            Token {
+                position: 2,
+                separator: false,
+                word: "/var/log/syslog",
+            },
+            Token {
+                position: 3,
+                separator: true,
+                word: " ",
+            },
+            Token {
+                position: 4,
+                separator: false,
+                word: "503",
+            },
+        ];
+
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
+        assert_eq!(
+            evaluate(r#"$id =~ "^ERR[0-9]+$""#, &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(
+            evaluate(r#"$id matches-regex "^ERR[0-9]+$""#, &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(
+            evaluate(r#"$id !~ "^ERR[0-9]+$""#, &tokens, &formats),
+            Ok(Some(HashSet::from([2, 4])))
+        );
+        assert_eq!(
+            evaluate(r#"$id =~ "^ERR[0-9]+$" and $integer > 500"#, &tokens, &formats),
+            Ok(Some(HashSet::from([0, 4])))
+        );
+        assert_eq!(evaluate(r#"$id =~ "^WARN""#, &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate(r#"$text =~ "^/var/log/""#, &tokens, &formats),
+            Ok(Some(HashSet::from([2])))
+        );
+        assert_eq!(
+            evaluate(r#"$text@2 =~ "log""#, &tokens, &formats),
+            Ok(Some(HashSet::from([2])))
+        );
+        assert_eq!(evaluate(r#"$text@1 =~ "log""#, &tokens, &formats), Ok(None));
+        assert_eq!(evaluate(r#"$2 =~ "\d""#, &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate(r#"$3 =~ "\d""#, &tokens, &formats),
+            Ok(Some(HashSet::from([4])))
+        );
+    }
+
+    #[test]
+    fn evaluate_text_expression() {
         // setup
         let tokens = vec![Token {
             position: 0,
@@ -880,25 +1463,310 @@ mod evaluation_tests {
 
         // exercise & verify
         assert_eq!(
-            expression::evaluate("$id == qpanda", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$text == qpanda", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(
+            evaluate("$word == qpanda", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$id contains and", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$text contains pan", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
+        assert_eq!(evaluate("$text != qpanda", &tokens, &formats), Ok(None));
+    }
+
+    #[test]
+    fn evaluate_correlation_expression() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("2021-10-16 ERROR 10.0.0.1 22 ok  192.168.1.1 80");
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
         assert_eq!(
-            expression::evaluate("$id starts-with qpa", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate(
+                "$ipv4Address in 10.0.0.0/8 followed-by $integer == 22",
+                &tokens,
+                &formats
+            ),
+            Ok(Some(HashSet::from([4, 6])))
         );
         assert_eq!(
-            expression::evaluate("$id ends-with nda", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate(
+                "$ipv4Address in 192.168.0.0/16 followed-by $integer == 22",
+                &tokens,
+                &formats
+            ),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("$id != qpanda", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipv4Address in 0.0.0.0/0 followed-by $integer > 0", &tokens, &formats),
+            Ok(Some(HashSet::from([4, 6, 11, 13])))
         );
+        assert_eq!(
+            evaluate("$date == 2021-10-16 followed-by $id == ERROR", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 2])))
+        );
+        assert_eq!(
+            evaluate("$id == ERROR followed-by $date == 2021-10-16", &tokens, &formats),
+            Ok(None)
+        );
+        assert_eq!(
+            evaluate("$id == ERROR within 4 $integer == 80", &tokens, &formats),
+            Ok(None)
+        );
+        assert_eq!(
+            evaluate("$id == ERROR within 5 $integer == 80", &tokens, &formats),
+            Ok(Some(HashSet::from([2, 13])))
+        );
+        assert_eq!(
+            evaluate("$integer == 80 within 2 $id == ok", &tokens, &formats),
+            Ok(Some(HashSet::from([8, 13])))
+        );
+        assert_eq!(
+            evaluate("$integer > 20 same-token $integer < 30", &tokens, &formats),
+            Ok(Some(HashSet::from([6])))
+        );
+        assert_eq!(
+            evaluate("$integer > 20 same-token $id == ok", &tokens, &formats),
+            Ok(None)
+        );
+        assert_eq!(
+            evaluate(
+                "$id == ok and $ipv4Address in 10.0.0.0/8 followed-by $integer == 22",
+                &tokens,
+                &formats
+            ),
+            Ok(Some(HashSet::from([4, 6, 8])))
+        );
+        assert_eq!(
+            evaluate(
+                "$date == 2021-10-16 followed-by $id == ERROR followed-by $ipv4Address in 10.0.0.0/8",
+                &tokens,
+                &formats
+            ),
+            Ok(Some(HashSet::from([0, 2, 4])))
+        );
+        assert_eq!(
+            evaluate(
+                "$ipv4Address in 0.0.0.0/0 followed-by $integer > 0 followed-by $id == ok",
+                &tokens,
+                &formats
+            ),
+            Ok(Some(HashSet::from([4, 6, 8])))
+        );
+        assert_eq!(
+            evaluate("not $id == root followed-by $id == ERROR", &tokens, &formats),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn evaluate_joined_expression() {
+        // setup
+        let separators = Separators::new(vec![" ", ":"]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("Oct 16 21:41:07 host 80");
+        let formats = Formats {
+            time: vec![String::from("%b %d %H:%M:%S")],
+            ..test_utils::default_formats()
+        };
+
+        // exercise & verify
+        assert_eq!(
+            evaluate(r#"$time > "Oct 16 21:00:00""#, &tokens, &formats),
+            Ok(Some(HashSet::from([0, 1, 2, 3, 4, 5, 6, 7, 8])))
+        );
+        assert_eq!(evaluate(r#"$time > "Oct 16 22:00:00""#, &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate(
+                r#"$time > "Oct 16 21:00:00" followed-by $id == host"#,
+                &tokens,
+                &formats
+            ),
+            Ok(Some(HashSet::from([0, 1, 2, 3, 4, 5, 6, 7, 8, 10])))
+        );
+    }
+
+    #[test]
+    fn evaluate_relative_expression() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("2021-10-15 21:00:00 2021-10-16T20:50:00+02:00 2021-10-16T18:45:00Z");
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
+        assert_eq!(
+            evaluate("$date == yesterday", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(evaluate("$date == today", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("$time > now-1h", &tokens, &formats),
+            Ok(Some(HashSet::from([2])))
+        );
+        assert_eq!(
+            evaluate("$dateTime > now-1h", &tokens, &formats),
+            Ok(Some(HashSet::from([4, 6])))
+        );
+        assert_eq!(
+            evaluate(
+                "$dateTime > -1h same-token $dateTime < 2021-10-16T20:48:00+02:00",
+                &tokens,
+                &formats
+            ),
+            Ok(Some(HashSet::from([6])))
+        );
+        assert_eq!(
+            evaluate("$timestamp >= now-55m", &tokens, &formats),
+            Ok(Some(HashSet::from([4])))
+        );
+    }
+
+    #[test]
+    fn evaluate_timestamp_expression() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("[16/Oct/2026:21:41:07 +0200] 2026-10-16T19:41:08Z GET");
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
+        assert_eq!(
+            evaluate("$timestamp == 2026-10-16T19:41:07Z", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 1, 2])))
+        );
+        assert_eq!(
+            evaluate(r#"$timestamp > "Fri, 16 Oct 2026 21:41:07 +0200""#, &tokens, &formats),
+            Ok(Some(HashSet::from([4])))
+        );
+        assert_eq!(
+            evaluate("count($timestamp >= 1792179667) == 2", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 1, 2, 4])))
+        );
+        assert_eq!(evaluate("$timestamp < 1792179667000", &tokens, &formats), Ok(None));
+    }
+
+    #[test]
+    fn evaluate_syslog_timestamp_expression() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("Oct 16 21:41:07 host sshd");
+        let formats = Formats {
+            now: DateTime::parse_from_rfc3339("2025-10-16T22:00:00Z").unwrap(),
+            ..test_utils::default_formats()
+        };
+
+        // exercise & verify
+        assert_eq!(
+            evaluate("$timestamp == 2025-10-16T21:41:07Z", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 1, 2, 3, 4])))
+        );
+        assert_eq!(evaluate("$timestamp < now-1h", &tokens, &formats), Ok(None));
+    }
+
+    #[test]
+    fn evaluate_joined_field_expression() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("[16/Oct/2026:21:41:07 +0000] x Oct 16 21:41:07");
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
+        assert_eq!(
+            evaluate("$timestamp@1 > 2020-01-01T00:00:00Z", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 1, 2])))
+        );
+        assert_eq!(
+            evaluate("$timestamp@2 > 2020-01-01T00:00:00Z", &tokens, &formats),
+            Ok(None)
+        );
+        assert_eq!(
+            evaluate("$timestamp@4 > 2020-01-01T00:00:00Z", &tokens, &formats),
+            Ok(Some(HashSet::from([6, 7, 8, 9, 10])))
+        );
+        assert_eq!(
+            evaluate("$timestamp@5 > 2020-01-01T00:00:00Z", &tokens, &formats),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn evaluate_quantifier_expression() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("1 50 200 10.0.0.1:80 10.0.0.2:81");
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
+        assert_eq!(evaluate("all($integer < 100)", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("all($integer < 1000)", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 2, 4])))
+        );
+        assert_eq!(evaluate("all($ipv6Address == ::1)", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("none($ipv6Address)", &tokens, &formats),
+            Ok(Some(HashSet::from([])))
+        );
+        assert_eq!(evaluate("none($ipv4SocketAddress)", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("none($integer > 500)", &tokens, &formats),
+            Ok(Some(HashSet::from([])))
+        );
+        assert_eq!(
+            evaluate("count($ipv4SocketAddress) >= 2", &tokens, &formats),
+            Ok(Some(HashSet::from([6, 8])))
+        );
+        assert_eq!(evaluate("count($ipv4SocketAddress) >= 3", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("count($integer > 10) == 2", &tokens, &formats),
+            Ok(Some(HashSet::from([2, 4])))
+        );
+        assert_eq!(
+            evaluate("count($ipv6Address) == 0", &tokens, &formats),
+            Ok(Some(HashSet::from([])))
+        );
+        assert_eq!(
+            evaluate(
+                "none($ipv6Address) and count(port($ipv4SocketAddress) > 80) == 1",
+                &tokens,
+                &formats
+            ),
+            Ok(Some(HashSet::from([8])))
+        );
+    }
+
+    #[test]
+    fn evaluate_string_expression() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("GET /var/log?q=\"x\" 404-not-found 1");
+
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
+        assert_eq!(
+            evaluate(r#"$text == "/var/log?q=\"x\"""#, &tokens, &formats),
+            Ok(Some(HashSet::from([2])))
+        );
+        assert_eq!(
+            evaluate(r#"$id == '404-not-found'"#, &tokens, &formats),
+            Ok(Some(HashSet::from([4])))
+        );
+        assert_eq!(
+            evaluate(r#"$text contains " and " or $4 == "1""#, &tokens, &formats),
+            Ok(Some(HashSet::from([6])))
+        );
+        assert_eq!(evaluate(r#"$text == "get""#, &tokens, &formats), Ok(None));
     }
 
     #[test]
@@ -931,52 +1799,46 @@ mod evaluation_tests {
 
         // exercise & verify
         assert_eq!(
-            expression::evaluate("$date == 2021-01-01", &tokens, &formats),
-            Ok(HashSet::from([0]))
-        );
-        assert_eq!(
-            expression::evaluate("$date != 2021-01-01", &tokens, &formats),
-            Ok(HashSet::from([]))
-        );
-        assert_eq!(
-            expression::evaluate("$date > 2000-01-01", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$date == 2021-01-01", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
+        assert_eq!(evaluate("$date != 2021-01-01", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("$time == 15:15:15", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$date > 2000-01-01", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$time != 15:15:15", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$time == 15:15:15", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
+        assert_eq!(evaluate("$time != 15:15:15", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("$time > 13:00:00", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$time > 13:00:00", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$dateTime == 2001-07-08T00:34:60.026490+09:30", &tokens, &formats),
-            Ok(HashSet::from([2]))
+            evaluate("$dateTime == 2001-07-08T00:34:60.026490+09:30", &tokens, &formats),
+            Ok(Some(HashSet::from([2])))
         );
         assert_eq!(
-            expression::evaluate("$dateTime != 2001-07-08T00:34:60.026490+09:30", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$dateTime != 2001-07-08T00:34:60.026490+09:30", &tokens, &formats),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("$dateTime > 2001-07-08T00:00:00.000000+09:30", &tokens, &formats),
-            Ok(HashSet::from([2]))
+            evaluate("$dateTime > 2001-07-08T00:00:00.000000+09:30", &tokens, &formats),
+            Ok(Some(HashSet::from([2])))
         );
         assert_eq!(
-            expression::evaluate("$localDateTime == 2001-07-08T00:34:60.026490", &tokens, &formats),
-            Ok(HashSet::from([3]))
+            evaluate("$localDateTime == 2001-07-08T00:34:60.026490", &tokens, &formats),
+            Ok(Some(HashSet::from([3])))
         );
         assert_eq!(
-            expression::evaluate("$localDateTime != 2001-07-08T00:34:60.026490", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$localDateTime != 2001-07-08T00:34:60.026490", &tokens, &formats),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("$localDateTime > 2001-07-08T00:00:00.000000", &tokens, &formats),
-            Ok(HashSet::from([3]))
+            evaluate("$localDateTime > 2001-07-08T00:00:00.000000", &tokens, &formats),
+            Ok(Some(HashSet::from([3])))
         );
     }
 
@@ -1000,68 +1862,62 @@ mod evaluation_tests {
 
         // exercise & verify
         assert_eq!(
-            expression::evaluate("$ipAddress == 8.8.8.8", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipAddress == 8.8.8.8", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$ipAddress == 2001:4860:4860::8888", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$ipAddress == 2001:4860:4860::8888", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipAddress != 8.8.8.8", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$ipAddress != 8.8.8.8", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipAddress != 2001:4860:4860::8888", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipAddress != 2001:4860:4860::8888", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$ipAddress in 8.8.8.0/24", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipAddress in 8.8.8.0/24", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$ipAddress in 2001:4860::/32", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$ipAddress in 2001:4860::/32", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipv4Address == 8.8.8.8", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipv4Address == 8.8.8.8", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
+        assert_eq!(evaluate("$ipv4Address != 8.8.8.8", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("$ipv4Address != 8.8.8.8", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipv4Address > 1.1.1.1", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$ipv4Address > 1.1.1.1", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipv4Address in 8.8.8.0/24", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
+        assert_eq!(evaluate("$ipv4Address not in 8.8.8.0/24", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("$ipv4Address in 8.8.8.0/24", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipv6Address == 2001:4860:4860::8888", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipv4Address not in 8.8.8.0/24", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipv6Address != 2001:4860:4860::8888", &tokens, &formats),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("$ipv6Address == 2001:4860:4860::8888", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$ipv6Address > 2001:4860:4860::8844", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipv6Address != 2001:4860:4860::8888", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipv6Address in 2001:4860::/32", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipv6Address > 2001:4860:4860::8844", &tokens, &formats),
-            Ok(HashSet::from([1]))
-        );
-        assert_eq!(
-            expression::evaluate("$ipv6Address in 2001:4860::/32", &tokens, &formats),
-            Ok(HashSet::from([1]))
-        );
-        assert_eq!(
-            expression::evaluate("$ipv6Address not in 2001:4860::/32", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipv6Address not in 2001:4860::/32", &tokens, &formats),
+            Ok(None)
         );
     }
 
@@ -1085,116 +1941,107 @@ mod evaluation_tests {
 
         // exercise & verify
         assert_eq!(
-            expression::evaluate("$ipSocketAddress == 8.8.8.8:53", &tokens, &formats),
-            Ok(HashSet::from([0]))
-        );
-        assert_eq!(
-            expression::evaluate("$ipSocketAddress == [2001:4860:4860::8888]:53", &tokens, &formats),
-            Ok(HashSet::from([1]))
-        );
-        assert_eq!(
-            expression::evaluate("$ipSocketAddress != 8.8.8.8:53", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$ipSocketAddress == 8.8.8.8:53", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$ipSocketAddress != [2001:4860:4860::8888]:53", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipSocketAddress == [2001:4860:4860::8888]:53", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("port($ipSocketAddress) == 53", &tokens, &formats),
-            Ok(HashSet::from([0, 1]))
+            evaluate("$ipSocketAddress != 8.8.8.8:53", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("port($ipSocketAddress) != 53", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipSocketAddress != [2001:4860:4860::8888]:53", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("ip($ipSocketAddress) == 8.8.8.8", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("port($ipSocketAddress) == 53", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 1])))
         );
+        assert_eq!(evaluate("port($ipSocketAddress) != 53", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("ip($ipSocketAddress) != 8.8.8.8", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("ip($ipSocketAddress) == 8.8.8.8", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("ip($ipSocketAddress) in 8.8.8.0/24", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("ip($ipSocketAddress) != 8.8.8.8", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("ip($ipSocketAddress) not in 8.8.8.0/24", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("ip($ipSocketAddress) in 8.8.8.0/24", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$ipv4SocketAddress == 8.8.8.8:53", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("ip($ipSocketAddress) not in 8.8.8.0/24", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipv4SocketAddress != 8.8.8.8:53", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipv4SocketAddress == 8.8.8.8:53", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$ipv4SocketAddress > 1.1.1.1:53", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipv4SocketAddress != 8.8.8.8:53", &tokens, &formats),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("port($ipv4SocketAddress) == 53", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipv4SocketAddress > 1.1.1.1:53", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("port($ipv4SocketAddress) != 53", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("port($ipv4SocketAddress) == 53", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
+        assert_eq!(evaluate("port($ipv4SocketAddress) != 53", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("ip($ipv4SocketAddress) == 8.8.8.8", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("ip($ipv4SocketAddress) == 8.8.8.8", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("ip($ipv4SocketAddress) != 8.8.8.8", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("ip($ipv4SocketAddress) != 8.8.8.8", &tokens, &formats),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("ip($ipv4SocketAddress) in 8.8.8.0/24", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("ip($ipv4SocketAddress) in 8.8.8.0/24", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("ip($ipv4SocketAddress) not in 8.8.8.0/24", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("ip($ipv4SocketAddress) not in 8.8.8.0/24", &tokens, &formats),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("$ipv6SocketAddress == [2001:4860:4860::8888]:53", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$ipv6SocketAddress == [2001:4860:4860::8888]:53", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipv6SocketAddress != [2001:4860:4860::8888]:53", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipv6SocketAddress != [2001:4860:4860::8888]:53", &tokens, &formats),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("$ipv6SocketAddress > [2001:4860:4860::8844]:53", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$ipv6SocketAddress > [2001:4860:4860::8844]:53", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("port($ipv6SocketAddress) == 53", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("port($ipv6SocketAddress) == 53", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
+        assert_eq!(evaluate("port($ipv6SocketAddress) != 53", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("port($ipv6SocketAddress) != 53", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("ip($ipv6SocketAddress) == 2001:4860:4860::8888", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("ip($ipv6SocketAddress) == 2001:4860:4860::8888", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("ip($ipv6SocketAddress) != 2001:4860:4860::8888", &tokens, &formats),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("ip($ipv6SocketAddress) != 2001:4860:4860::8888", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("ip($ipv6SocketAddress) in 2001:4860::/32", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("ip($ipv6SocketAddress) in 2001:4860::/32", &tokens, &formats),
-            Ok(HashSet::from([1]))
-        );
-        assert_eq!(
-            expression::evaluate("ip($ipv6SocketAddress) not in 2001:4860::/32", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("ip($ipv6SocketAddress) not in 2001:4860::/32", &tokens, &formats),
+            Ok(None)
         );
     }
 
@@ -1218,37 +2065,31 @@ mod evaluation_tests {
 
         // exercise & verify
         assert_eq!(
-            expression::evaluate("$ipNetwork == 10.1.1.0/24", &tokens, &formats),
-            Ok(HashSet::from([0]))
-        );
-        assert_eq!(
-            expression::evaluate("$ipNetwork != 10.1.1.0/24", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$ipNetwork == 10.1.1.0/24", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$ipNetwork == fd00::/32", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$ipNetwork != 10.1.1.0/24", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipNetwork != fd00::/32", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipNetwork == fd00::/32", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$ipv4Network == 10.1.1.0/24", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$ipNetwork != fd00::/32", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$ipv4Network != 10.1.1.0/24", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipv4Network == 10.1.1.0/24", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
+        assert_eq!(evaluate("$ipv4Network != 10.1.1.0/24", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("$ipv6Network == fd00::/32", &tokens, &formats),
-            Ok(HashSet::from([1]))
-        );
-        assert_eq!(
-            expression::evaluate("$ipv6Network != fd00::/32", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$ipv6Network == fd00::/32", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
+        assert_eq!(evaluate("$ipv6Network != fd00::/32", &tokens, &formats), Ok(None));
     }
 
     #[test]
@@ -1264,24 +2105,80 @@ mod evaluation_tests {
 
         // exercise & verify
         assert_eq!(
-            expression::evaluate("$semanticVersion == 1.2.3", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("$semanticVersion == 1.2.3", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(evaluate("$semanticVersion != 1.2.3", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("$semanticVersion > 1.0.0", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(
+            evaluate("$semanticVersion matches >=1.2.3,<1.8.0", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+        assert_eq!(
+            evaluate("$semanticVersion matches ~1.2.3", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
+        );
+    }
+
+    #[test]
+    fn evaluate_field_expression() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("tcp  1 0 109.74.193.253:22 79.131.135.223:64917 ESTABLISHED");
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
+        assert_eq!(evaluate("$1 == tcp", &tokens, &formats), Ok(Some(HashSet::from([0]))));
+        assert_eq!(evaluate("$2 == tcp", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("$6 == ESTABLISHED", &tokens, &formats),
+            Ok(Some(HashSet::from([11])))
+        );
+        assert_eq!(evaluate("$7 == ESTABLISHED", &tokens, &formats), Ok(None));
+        assert_eq!(evaluate("$2 == 1", &tokens, &formats), Ok(Some(HashSet::from([3]))));
+        assert_eq!(evaluate("$3 == 1", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("$4 starts-with 109.74", &tokens, &formats),
+            Ok(Some(HashSet::from([7])))
+        );
+        assert_eq!(
+            evaluate("$integer > 0", &tokens, &formats),
+            Ok(Some(HashSet::from([3])))
+        );
+        assert_eq!(
+            evaluate("$integer@2 > 0", &tokens, &formats),
+            Ok(Some(HashSet::from([3])))
+        );
+        assert_eq!(evaluate("$integer@3 > 0", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("$integer@3 == 0", &tokens, &formats),
+            Ok(Some(HashSet::from([5])))
+        );
+        assert_eq!(evaluate("$integer@1 == 0", &tokens, &formats), Ok(None));
+        assert_eq!(
+            evaluate("$integer[1] >= 0", &tokens, &formats),
+            Ok(Some(HashSet::from([3])))
         );
         assert_eq!(
-            expression::evaluate("$semanticVersion != 1.2.3", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$integer[2] >= 0", &tokens, &formats),
+            Ok(Some(HashSet::from([5])))
         );
+        assert_eq!(evaluate("$integer[3] >= 0", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("$semanticVersion > 1.0.0", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("port($ipv4SocketAddress) == 22", &tokens, &formats),
+            Ok(Some(HashSet::from([7])))
         );
         assert_eq!(
-            expression::evaluate("$semanticVersion matches >=1.2.3,<1.8.0", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("port($ipv4SocketAddress@5) == 22", &tokens, &formats),
+            Ok(None)
         );
         assert_eq!(
-            expression::evaluate("$semanticVersion matches ~1.2.3", &tokens, &formats),
-            Ok(HashSet::from([0]))
+            evaluate("port($ipv4SocketAddress[2]) > 1024", &tokens, &formats),
+            Ok(Some(HashSet::from([9])))
         );
     }
 
@@ -1309,80 +2206,233 @@ mod evaluation_tests {
 
         // exercise & verify
         assert_eq!(
-            expression::evaluate("$integer == 9 and $float == 5.5", &tokens, &formats),
-            Ok(HashSet::from([1, 2]))
+            evaluate("$integer == 9 and $float == 5.5", &tokens, &formats),
+            Ok(Some(HashSet::from([1, 2])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 9 or $float == 5.5", &tokens, &formats),
-            Ok(HashSet::from([1, 2]))
+            evaluate("$integer == 9 or $float == 5.5", &tokens, &formats),
+            Ok(Some(HashSet::from([1, 2])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 9 or $float == 8.8", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$integer == 9 or $float == 8.8", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 8 or $float == 5.5", &tokens, &formats),
-            Ok(HashSet::from([2]))
+            evaluate("$integer == 8 or $float == 5.5", &tokens, &formats),
+            Ok(Some(HashSet::from([2])))
         );
+        assert_eq!(evaluate("$integer == 8 or $float == 6.6", &tokens, &formats), Ok(None));
+        assert_eq!(evaluate("$integer == 9 and $integer == 8", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("$integer == 8 or $float == 6.6", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$integer == 9 and ($float == 5.5 or $id == a1)", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 1, 2])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 9 and $integer == 8", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate("$integer == 9 and ($float == 5.5 or $id == b1)", &tokens, &formats),
+            Ok(Some(HashSet::from([1, 2])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 9 and ($float == 5.5 or $id == a1)", &tokens, &formats),
-            Ok(HashSet::from([0, 1, 2]))
+            evaluate("$integer == 9 and ($float != 5.5 or $id == a1)", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 1])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 9 and ($float == 5.5 or $id == b1)", &tokens, &formats),
-            Ok(HashSet::from([1, 2]))
+            evaluate("$integer == 9 or ($float == 8.8 or $id == b1)", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 9 and ($float != 5.5 or $id == a1)", &tokens, &formats),
-            Ok(HashSet::from([0, 1]))
+            evaluate("$integer == 9 or ($float != 5.5)", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
+        );
+    }
+
+    #[test]
+    fn evaluate_operator_precedence() {
+        // setup
+        let tokens = vec![
+            Token {
+                position: 0,
+                separator: false,
+                word: "1",
+            },
+            Token {
+                position: 1,
+                separator: false,
+                word: "2.2",
+            },
+        ];
+        let formats = test_utils::default_formats();
+
+        // exercise & verify
+        assert_eq!(
+            evaluate("$integer == 0 and $integer == 1 or $float == 2.2", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 9 or ($float == 8.8 or $id == b1)", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("($integer == 0 and $integer == 1) or $float == 2.2", &tokens, &formats),
+            Ok(Some(HashSet::from([1])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 9 or ($float != 5.5)", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("$integer == 0 and ($integer == 1 or $float == 2.2)", &tokens, &formats),
+            Ok(None)
+        );
+        assert_eq!(
+            evaluate(
+                "not $integer == 0 and $integer == 1 or $float == 2.2",
+                &tokens,
+                &formats
+            ),
+            Ok(Some(HashSet::from([0, 1])))
+        );
+        assert_eq!(
+            evaluate(
+                "not ($integer == 0 and $integer == 1 or $float == 2.2)",
+                &tokens,
+                &formats
+            ),
+            Ok(None)
+        );
+        assert_eq!(
+            evaluate("$integer == 0 or $integer == 1 and $float == 2.2", &tokens, &formats),
+            Ok(Some(HashSet::from([0, 1])))
         );
     }
 
     #[test]
-    fn evaluate_operator_precedence() {
+    fn evaluate_not_expression() {
         // setup
         let tokens = vec![
             Token {
                 position: 0,
                 separator: false,
-                word: "1",
+                word: "root",
             },
             Token {
                 position: 1,
                 separator: false,
-                word: "2.2",
+                word: "9",
+            },
+            Token {
+                position: 2,
+                separator: false,
+                word: "0.5",
             },
         ];
         let formats = test_utils::default_formats();
 
         // exercise & verify
+        assert_eq!(evaluate("not $id == root", &tokens, &formats), Ok(None));
         assert_eq!(
-            expression::evaluate("$integer == 0 and $integer == 1 or $float == 2.2", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("!$id == qpanda", &tokens, &formats),
+            Ok(Some(HashSet::from([])))
         );
         assert_eq!(
-            expression::evaluate("($integer == 0 and $integer == 1) or $float == 2.2", &tokens, &formats),
-            Ok(HashSet::from([1]))
+            evaluate("not not $id == root", &tokens, &formats),
+            Ok(Some(HashSet::from([0])))
         );
         assert_eq!(
-            expression::evaluate("$integer == 0 and ($integer == 1 or $float == 2.2)", &tokens, &formats),
-            Ok(HashSet::from([]))
+            evaluate(
+                "not ($id == qpanda) and $integer > 5 and $float < 1.0",
+                &tokens,
+                &formats
+            ),
+            Ok(Some(HashSet::from([1, 2])))
         );
+        assert_eq!(
+            evaluate("not ($id == root) and $integer > 5 and $float < 1.0", &tokens, &formats),
+            Ok(None)
+        );
+        assert_eq!(
+            evaluate("not $id == qpanda or $integer > 10 or $float > 10.0", &tokens, &formats),
+            Ok(Some(HashSet::from([])))
+        );
+    }
+
+    #[test]
+    fn evaluate_compiled_expression_per_line() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let formats = test_utils::default_formats();
+        let expression = "$integer > 5 and $ipv4Address == 10.0.0.1 or $id == ERROR";
+        let compiled = expression::compile(expression, &formats).unwrap();
+
+        // positions matched by evaluating the expression text for each line before it was compiled once
+        let lines = vec![
+            ("ERROR 10.0.0.1 9", HashSet::from([0, 2, 4])),
+            ("INFO 10.0.0.2 9", HashSet::from([])),
+            ("WARN 10.0.0.1 3", HashSet::from([])),
+            ("INFO 10.0.0.1 7", HashSet::from([2, 4])),
+            ("ERROR", HashSet::from([0])),
+            ("", HashSet::from([])),
+        ];
+
+        for (line, expected) in lines {
+            let tokens = tokenizer.tokens(line);
+
+            // exercise
+            let matches = compiled.evaluate(&tokens);
+            let recompiled_matches = evaluate(expression, &tokens, &formats).unwrap();
+
+            // verify
+            assert_eq!(expected, matches.clone().unwrap_or_default());
+            assert_eq!(recompiled_matches, matches);
+        }
+    }
+
+    #[test]
+    fn evaluate_expression_types() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("ERROR 10.0.0.1 9");
+        let formats = test_utils::default_formats();
+        let expression = expression::compile(
+            "$text == ERROR and not $ipv4Address == 10.0.0.2 and ($integer == 9 or $id == INFO)",
+            &formats,
+        )
+        .unwrap();
+        let mut types = Types::new();
+
+        // exercise
+        let matches = expression.evaluate_types(&tokens, Some(&mut types));
+
+        // verify
+        assert_eq!(matches, Some(HashSet::from([0, 4])));
+        assert_eq!(types, Types::from([(0, "$text"), (4, "$integer")]));
+    }
+
+    #[test]
+    fn evaluate_expression_types_of_unmatched_branches() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("9 x 9 b");
+        let formats = test_utils::default_formats();
+        let and = expression::compile("($integer == 9 and $id == a) or $float > 1.0", &formats).unwrap();
+        let correlation =
+            expression::compile("($integer == 9 followed-by $id == b) or $float > 1.0", &formats).unwrap();
+        let mut and_types = Types::new();
+        let mut correlation_types = Types::new();
+
+        // exercise
+        let and_matches = and.evaluate_types(&tokens, Some(&mut and_types));
+        let correlation_matches = correlation.evaluate_types(&tokens, Some(&mut correlation_types));
+
+        // verify
+        assert_eq!(and_matches, Some(HashSet::from([0, 4])));
+        assert_eq!(and_types, Types::from([(0, "$float"), (4, "$float")]));
+        assert_eq!(correlation_matches, Some(HashSet::from([0, 4, 6])));
+        assert_eq!(
+            correlation_types,
+            Types::from([(0, "$float"), (4, "$integer"), (6, "$id")])
+        );
+    }
+
+    fn evaluate(
+        expression: &str,
+        tokens: &[Token],
+        formats: &Formats,
+    ) -> Result<Option<HashSet<Position>>, ParseError<LineCol>> {
+        expression::compile(expression, formats).map(|expression| expression.evaluate(tokens))
     }
 }
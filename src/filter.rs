@@ -1,10 +1,27 @@
 use ansi_term::Colour;
 use anyhow::{anyhow, Context, Error};
-use std::collections::HashSet;
-use std::io::{BufRead, BufReader, LineWriter, Read, Write};
+use chrono::{DateTime, FixedOffset};
+use encoding_rs::{DecoderResult, Encoding};
+use serde_json::{json, Map, Value};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::io::{LineWriter, Read, Write};
 use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::expression::expression::evaluate;
+use crate::aggregation::Aggregation;
+use crate::diagnostics::Diagnostics;
+use crate::evaluator::Evaluate;
+use crate::evaluator::Expression;
+use crate::evaluator::Types;
+use crate::expression::expression::{aggregation, compile, projection};
+use crate::follow::{Follow, Report, POLL_INTERVAL};
+use crate::input::Input;
+use crate::projection::{self, Projection};
+use crate::record::{RecordSeparator, Records};
 use crate::tokenizer::Position;
 use crate::tokenizer::Token;
 use crate::tokenizer::Tokenizer;
@@ -12,12 +29,16 @@ use crate::tokenizer::Tokenizer;
 pub const FILTER: &str = "filter";
 pub const HIGHLIGHT: &str = "highlight";
 pub const FILTER_HIGHLIGHT: &str = "filter-and-highlight";
+pub const REJECT: &str = "reject";
+pub const ONLY_MATCHING: &str = "only-matching";
 
 #[derive(PartialEq)]
 pub enum Mode {
     Filter,
     Highlight(Colour),
     FilterHighlight(Colour),
+    Reject,
+    OnlyMatching,
 }
 
 impl FromStr for Mode {
@@ -28,6 +49,8 @@ impl FromStr for Mode {
             FILTER => Ok(Mode::Filter),
             HIGHLIGHT => Ok(Mode::Highlight(Colour::Red)),
             FILTER_HIGHLIGHT => Ok(Mode::FilterHighlight(Colour::Red)),
+            REJECT => Ok(Mode::Reject),
+            ONLY_MATCHING => Ok(Mode::OnlyMatching),
             _ => Err(anyhow!("invalid mode '{}'", mode)),
         }
     }
@@ -39,6 +62,63 @@ impl Mode {
             Self::Filter => false,
             Self::Highlight(_) => true,
             Self::FilterHighlight(_) => true,
+            Self::Reject => false,
+            Self::OnlyMatching => false,
+        }
+    }
+}
+
+pub const TEXT: &str = "text";
+pub const JSON: &str = "json";
+pub const NDJSON: &str = "ndjson";
+pub const TSV: &str = "tsv";
+pub const CSV: &str = "csv";
+
+#[derive(PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Ndjson,
+    Tsv,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self, Error> {
+        match format {
+            TEXT => Ok(Format::Text),
+            JSON => Ok(Format::Json),
+            NDJSON => Ok(Format::Ndjson),
+            TSV => Ok(Format::Tsv),
+            CSV => Ok(Format::Csv),
+            _ => Err(anyhow!("invalid output format '{}'", format)),
+        }
+    }
+}
+
+pub const SKIP: &str = "skip";
+pub const LOSSY: &str = "lossy";
+pub const FAIL: &str = "fail";
+
+// policy for input-files with NUL bytes or bytes which are invalid in the input encoding
+#[derive(PartialEq)]
+pub enum BinaryFiles {
+    Skip,
+    Lossy,
+    Fail,
+}
+
+impl FromStr for BinaryFiles {
+    type Err = Error;
+
+    fn from_str(binary_files: &str) -> Result<Self, Error> {
+        match binary_files {
+            SKIP => Ok(BinaryFiles::Skip),
+            LOSSY => Ok(BinaryFiles::Lossy),
+            FAIL => Ok(BinaryFiles::Fail),
+            _ => Err(anyhow!("invalid binary-files policy '{}'", binary_files)),
         }
     }
 }
@@ -48,92 +128,535 @@ pub const TIME_FORMAT: &str = "%T";
 pub const DATE_TIME_FORMAT: &str = "%+";
 pub const LOCAL_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
 
+// formats are tried in order until one of them parses; relative literals such as 'now-1h' are resolved against now
 pub struct Formats {
-    pub date: String,
-    pub time: String,
-    pub date_time: String,
-    pub local_date_time: String,
+    pub date: Vec<String>,
+    pub time: Vec<String>,
+    pub date_time: Vec<String>,
+    pub local_date_time: Vec<String>,
+    pub now: DateTime<FixedOffset>,
 }
 
 pub struct Settings {
     pub formats: Formats,
     pub mode: Mode,
     pub count: bool,
+    pub before_context: usize,
+    pub after_context: usize,
+    pub line_number: bool,
+    pub byte_offset: bool,
+    pub delimiter: Option<String>,
+    pub format: Format,
+    pub print: Option<String>,
+    pub print_all: bool,
+    pub aggregate: Option<String>,
+    pub follow: bool,
+    pub count_interval: Option<Duration>,
+    pub decompress: bool,
+    pub encoding: &'static Encoding,
+    pub binary_files: BinaryFiles,
+    pub record_separator: RecordSeparator,
 }
 
 pub struct Filter<'a> {
     tokenizer: &'a Tokenizer,
-    expression: &'a str,
+    expression: Expression,
+    projections: Vec<Projection>,
+    aggregation: Option<RefCell<Aggregation>>,
     settings: &'a Settings,
+    records: Cell<usize>,
+    progress: RefCell<Lines>,
 }
 
+#[derive(Clone)]
 pub struct Lines {
     pub processed: usize,
     pub matched: usize,
+    pub rejected: usize,
+}
+
+impl Lines {
+    pub fn new() -> Self {
+        Lines {
+            processed: 0,
+            matched: 0,
+            rejected: 0,
+        }
+    }
+
+    pub fn count(&mut self, matched: bool) {
+        match matched {
+            true => self.matched += 1,
+            false => self.rejected += 1,
+        }
+        self.processed += 1;
+    }
+
+    pub fn add(&mut self, lines: &Lines) {
+        self.processed += lines.processed;
+        self.matched += lines.matched;
+        self.rejected += lines.rejected;
+    }
+}
+
+impl fmt::Display for Lines {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} line(s) processed, {} line(s) matched, {} line(s) rejected",
+            self.processed, self.matched, self.rejected
+        )
+    }
+}
+
+// an input-file which fails while being read is reported as a warning and the remaining inputs are filtered
+#[derive(Debug)]
+struct ReadError(usize);
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "Unable to read line '{}' of input-file", self.0)
+    }
 }
 
 impl<'a> Filter<'a> {
-    pub fn new(tokenizer: &'a Tokenizer, expression: &'a str, settings: &'a Settings) -> Result<Self, Error> {
-        evaluate(expression, &vec![], &settings.formats).context(format!("Invalid expression '{}'", expression))?;
+    pub fn new(tokenizer: &'a Tokenizer, expression: &str, settings: &'a Settings) -> Result<Self, Error> {
+        let expression = compile(expression, &settings.formats)
+            .map_err(|error| Diagnostics::diagnose(expression, &error))
+            .context(format!("Invalid expression '{}'", expression))?;
+        let projections = match &settings.print {
+            None => Vec::new(),
+            Some(print) => projection(print, &settings.formats)
+                .map_err(|error| Diagnostics::diagnose(print, &error))
+                .context(format!("Invalid print projection '{}'", print))?,
+        };
+        let aggregation = match &settings.aggregate {
+            None => None,
+            Some(aggregate) => Some(RefCell::new(
+                aggregation(aggregate, &settings.formats)
+                    .map_err(|error| Diagnostics::diagnose(aggregate, &error))
+                    .context(format!("Invalid aggregation '{}'", aggregate))?,
+            )),
+        };
 
         Ok(Filter {
             tokenizer: tokenizer,
             expression: expression,
+            projections: projections,
+            aggregation: aggregation,
             settings: settings,
+            records: Cell::new(0),
+            progress: RefCell::new(Lines::new()),
         })
     }
 
-    pub fn filter(&self, read: &mut dyn Read, write: &mut dyn Write) -> Result<Lines, Error> {
-        let mut lines = Lines {
-            processed: 0,
-            matched: 0,
+    // unreadable inputs are reported as warnings; lines are prefixed with the input name if there are multiple inputs
+    pub fn filter_inputs(&self, inputs: &[Result<Input, Error>], write: &mut dyn Write) -> Result<Lines, Error> {
+        let mut lines = Lines::new();
+        let named = inputs.len() > 1;
+        if self.settings.format == Format::Json {
+            write.write_all(b"[").context("Unable to write to output-file")?;
+        }
+        // SIGUSR1 is handled only while following so that it still terminates other runs
+        let mut report = match self.settings.follow {
+            true => Some(Report::new(self.settings.count_interval)?),
+            false => None,
         };
+        for input in inputs {
+            let opened = match input {
+                Ok(file @ Input::File(path)) if self.settings.follow => {
+                    let tick = Box::new(|| self.report(&mut report));
+                    Follow::new(path, POLL_INTERVAL, Arc::new(AtomicBool::new(false)), tick)
+                        .map(|follow| (file.name(), Box::new(follow) as Box<dyn Read>))
+                }
+                Ok(input) => input.open(self.settings.decompress).map(|read| (input.name(), read)),
+                Err(error) => Err(anyhow!("{:#}", error)),
+            };
+            let (name, mut read) = match opened {
+                Ok(opened) => opened,
+                Err(error) => {
+                    eprintln!("Warning: {:#}", error);
+                    continue;
+                }
+            };
 
-        let reader = BufReader::new(read);
-        let mut writer = LineWriter::new(write);
-        for input_line in reader.lines() {
-            let input_line = input_line.context(format!("Unable to read line '{}' of input-file", lines.processed))?;
-            let tokens = self.tokenizer.tokens(&input_line);
-            let matches = evaluate(self.expression, &tokens, &self.settings.formats).context(format!(
-                "Evaluating expression '{}' for line '{}' of input-file failed",
-                self.expression, lines.processed
-            ))?;
-            if let Some(output_line) = self.output_line(tokens, &matches) {
-                writer
-                    .write_all(output_line.as_bytes())
-                    .context(format!("Unable to write to output-file"))?;
-                writer
-                    .write_all(b"\n")
-                    .context(format!("Unable to write to output-file"))?;
+            let input_lines = match self.filter_lines(Some(name.as_str()).filter(|_| named), &mut read, write) {
+                Ok(input_lines) => input_lines,
+                Err(error) if error.downcast_ref::<ReadError>().is_some() => {
+                    eprintln!("Warning: {}: {:#}", name, error);
+                    self.progress.borrow().clone()
+                }
+                Err(error) => return Err(error),
+            };
+            if self.settings.count && named {
+                println!("\n{}: {}", name, input_lines);
             }
+            lines.add(&input_lines);
+        }
 
-            if !matches.is_empty() {
-                lines.matched += 1;
-            }
-            lines.processed += 1;
+        if self.settings.format == Format::Json {
+            write.write_all(b"\n]\n").context("Unable to write to output-file")?;
+        }
+        if let Some(aggregation) = &self.aggregation {
+            self.write_table(write, &aggregation.borrow())?;
         }
 
         if self.settings.count {
-            println!(
-                "\n{} line(s) processed, {} line(s) matched",
-                lines.processed, lines.matched
-            );
+            println!("\n{}", lines);
         }
 
         Ok(lines)
     }
 
-    fn output_line(&self, tokens: Vec<Token>, matches: &HashSet<Position>) -> Option<String> {
-        match self.settings.mode {
-            Mode::Filter => match matches.is_empty() {
-                true => None,
-                false => Some(self.normal_text(tokens)),
-            },
-            Mode::Highlight(colour) => Some(self.highlighted_text(tokens, matches, colour)),
-            Mode::FilterHighlight(colour) => match matches.is_empty() {
-                true => None,
-                false => Some(self.highlighted_text(tokens, matches, colour)),
-            },
+    fn filter_lines(&self, name: Option<&str>, read: &mut dyn Read, write: &mut dyn Write) -> Result<Lines, Error> {
+        self.progress.replace(Lines::new());
+
+        let mut before: VecDeque<(usize, usize, String, Vec<u8>)> = VecDeque::new();
+        let mut after = 0;
+        let mut last_written = None;
+        let mut offset = 0;
+
+        let mut records = Records::new(read, &self.settings.record_separator);
+        let mut writer = LineWriter::new(write);
+        let mut buffer = Vec::new();
+        let mut binary_matched = false;
+        // like grep, an input-file is classified as binary from its first buffer before any of its lines are written
+        let mut binary = match self.settings.binary_files {
+            BinaryFiles::Skip => self.binary(records.peek().context(ReadError(0))?),
+            _ => false,
+        };
+        loop {
+            let number = self.progress.borrow().processed;
+            let length = records.read(&mut buffer).context(ReadError(number))?;
+            if length == 0 {
+                break;
+            }
+
+            let terminator = records.terminator();
+            let separator = records.last_separator();
+            let (input_line, invalid) = self.settings.encoding.decode_without_bom_handling(&buffer);
+            if invalid || buffer.contains(&0) {
+                match self.settings.binary_files {
+                    BinaryFiles::Skip => binary = true,
+                    BinaryFiles::Lossy => {}
+                    BinaryFiles::Fail => {
+                        return Err(anyhow!(
+                            "Line '{}' of input-file is binary or invalid {}",
+                            number,
+                            self.settings.encoding.name()
+                        ));
+                    }
+                }
+            }
+
+            let input_line = input_line.as_ref();
+            let tokens = self.tokenizer.tokens(input_line);
+            // types are only collected for lines which are written as JSON records
+            let json = self.settings.format == Format::Json || self.settings.format == Format::Ndjson;
+            let mut types = match json && !binary && self.aggregation.is_none() {
+                true => Some(Types::new()),
+                false => None,
+            };
+            let matches = match types.as_mut() {
+                Some(types) => self.expression.evaluate_types(&tokens, Some(types)),
+                None => self.expression.evaluate(&tokens),
+            };
+            if binary {
+                // like grep, lines of binary input-files are not printed once binary data is found
+                binary_matched |= matches.is_some() != (self.settings.mode == Mode::Reject);
+            } else if let Some(aggregation) = &self.aggregation {
+                if matches.is_some() {
+                    aggregation.borrow_mut().add(&tokens);
+                }
+            } else if let Some(types) = &types {
+                if let Some(matches) = &matches {
+                    self.write_record(&mut writer, name, number, offset, input_line, &tokens, matches, types)?;
+                }
+            } else if self.settings.format == Format::Tsv || self.settings.format == Format::Csv {
+                if matches.is_some() {
+                    self.write_row(&mut writer, name, number, offset, &tokens, separator)?;
+                }
+            } else if self.settings.mode == Mode::OnlyMatching {
+                if let Some(matches) = &matches {
+                    self.write_tokens(&mut writer, name, number, offset, &tokens, matches, separator)?;
+                }
+            } else {
+                match self.output_line(tokens, &matches) {
+                    Some(output_line) => {
+                        for (number, context_offset, context_line, context_terminator) in before.drain(..) {
+                            let prefix = self.prefix(name, number, context_offset, '-');
+                            self.write_line(
+                                &mut writer,
+                                number,
+                                &prefix,
+                                &context_line,
+                                &context_terminator,
+                                separator,
+                                &mut last_written,
+                            )?;
+                        }
+                        let prefix = self.prefix(name, number, offset, ':');
+                        self.write_line(
+                            &mut writer,
+                            number,
+                            &prefix,
+                            &output_line,
+                            terminator,
+                            separator,
+                            &mut last_written,
+                        )?;
+                        after = self.settings.after_context;
+                    }
+                    None if after > 0 => {
+                        let prefix = self.prefix(name, number, offset, '-');
+                        self.write_line(
+                            &mut writer,
+                            number,
+                            &prefix,
+                            input_line,
+                            terminator,
+                            separator,
+                            &mut last_written,
+                        )?;
+                        after -= 1;
+                    }
+                    None if self.settings.before_context > 0 => {
+                        before.push_back((number, offset, input_line.to_string(), terminator.to_vec()));
+                        if before.len() > self.settings.before_context {
+                            before.pop_front();
+                        }
+                    }
+                    None => {}
+                }
+            }
+
+            self.progress.borrow_mut().count(matches.is_some());
+            offset += length;
+        }
+
+        if binary_matched {
+            let message = match name {
+                Some(name) => format!("Binary file {} matches", name),
+                None => String::from("Binary file matches"),
+            };
+            match self.settings.format {
+                Format::Text => writeln!(writer, "{}", message).context("Unable to write to output-file")?,
+                _ => eprintln!("{}", message),
+            }
+        }
+
+        Ok(self.progress.borrow().clone())
+    }
+
+    // prints the line counts of the input being filtered if a summary is due
+    // NUL bytes separate records rather than mark binary data if records are NUL separated, an incomplete character at
+    // the end of the bytes is not invalid as it may continue in the bytes read next
+    fn binary(&self, bytes: &[u8]) -> bool {
+        if bytes.contains(&0) && !matches!(self.settings.record_separator, RecordSeparator::Null) {
+            return true;
+        }
+
+        let mut decoder = self.settings.encoding.new_decoder_without_bom_handling();
+        let mut text = String::with_capacity(
+            decoder
+                .max_utf8_buffer_length_without_replacement(bytes.len())
+                .unwrap_or(0),
+        );
+        let (result, _) = decoder.decode_to_string_without_replacement(bytes, &mut text, false);
+        return matches!(result, DecoderResult::Malformed(_, _));
+    }
+
+    fn report(&self, report: &mut Option<Report>) {
+        if self.settings.count && report.as_mut().is_some_and(|report| report.due()) {
+            println!("\n{}", self.progress.borrow());
+        }
+    }
+
+    // grep-style prefix using ':' for output lines and '-' for context lines
+    fn prefix(&self, name: Option<&str>, number: usize, offset: usize, delimiter: char) -> String {
+        let mut prefix = String::new();
+        if let Some(name) = name {
+            prefix.push_str(&format!("{}{}", name, delimiter));
+        }
+        if self.settings.line_number || name.is_some() {
+            prefix.push_str(&format!("{}{}", number + 1, delimiter));
+        }
+        if self.settings.byte_offset {
+            prefix.push_str(&format!("{}{}", offset, delimiter));
+        }
+
+        return prefix;
+    }
+
+    // writes matched tokens joined by the delimiter or each on a separate line prefixed with its token position (field)
+    #[allow(clippy::too_many_arguments)]
+    fn write_tokens(
+        &self,
+        writer: &mut dyn Write,
+        name: Option<&str>,
+        number: usize,
+        offset: usize,
+        tokens: &[Token],
+        matches: &HashSet<Position>,
+        terminator: &[u8],
+    ) -> Result<(), Error> {
+        if let Some(delimiter) = &self.settings.delimiter {
+            let words: Vec<String> = units(tokens, matches).into_iter().map(|unit| unit.text).collect();
+            if !words.is_empty() {
+                let prefix = self.prefix(name, number, offset, ':');
+                write!(writer, "{}{}", prefix, words.join(delimiter)).context("Unable to write to output-file")?;
+                writer.write_all(terminator).context("Unable to write to output-file")?;
+            }
+            return Ok(());
+        }
+
+        for unit in units(tokens, matches) {
+            let prefix = self.prefix(name, number, offset + unit.start, ':');
+            match self.settings.line_number {
+                true => write!(writer, "{}{}:{}", prefix, unit.field, unit.text),
+                false => write!(writer, "{}{}", prefix, unit.text),
+            }
+            .context("Unable to write to output-file")?;
+            writer.write_all(terminator).context("Unable to write to output-file")?;
+        }
+
+        Ok(())
+    }
+
+    // writes the line and its matched tokens as JSON object, records in a JSON array are separated by commas
+    #[allow(clippy::too_many_arguments)]
+    fn write_record(
+        &self,
+        writer: &mut dyn Write,
+        name: Option<&str>,
+        number: usize,
+        offset: usize,
+        line: &str,
+        tokens: &[Token],
+        matches: &HashSet<Position>,
+        types: &Types,
+    ) -> Result<(), Error> {
+        let tokens_json: Vec<Value> = units(tokens, matches)
+            .into_iter()
+            .map(|unit| {
+                json!({
+                    "position": unit.field,
+                    "start": unit.start,
+                    "end": unit.start + unit.text.len(),
+                    "token": unit.text,
+                    "type": types.get(&unit.position),
+                })
+            })
+            .collect();
+
+        let mut record = Map::new();
+        if let Some(name) = name {
+            record.insert(String::from("file"), json!(name));
+        }
+        record.insert(String::from("line"), json!(number + 1));
+        record.insert(String::from("offset"), json!(offset));
+        record.insert(String::from("text"), json!(line));
+        record.insert(String::from("matches"), json!(tokens_json));
+
+        let separator = match (&self.settings.format, self.records.get()) {
+            (Format::Json, 0) => "\n",
+            (Format::Json, _) => ",\n",
+            _ => "",
+        };
+        write!(writer, "{}{}", separator, Value::Object(record)).context("Unable to write to output-file")?;
+        if self.settings.format == Format::Ndjson {
+            writer.write_all(b"\n").context("Unable to write to output-file")?;
+        }
+        self.records.set(self.records.get() + 1);
+
+        Ok(())
+    }
+
+    // writes the first (or all) values of each projection as TSV or CSV row, prefixed with name, number, and offset
+    fn write_row(
+        &self,
+        writer: &mut dyn Write,
+        name: Option<&str>,
+        number: usize,
+        offset: usize,
+        tokens: &[Token],
+        terminator: &[u8],
+    ) -> Result<(), Error> {
+        let mut values = Vec::new();
+        if let Some(name) = name {
+            values.push(name.to_string());
+        }
+        if self.settings.line_number || name.is_some() {
+            values.push((number + 1).to_string());
+        }
+        if self.settings.byte_offset {
+            values.push(offset.to_string());
+        }
+        for projection in &self.projections {
+            let projected = projection.values(tokens);
+            match self.settings.print_all {
+                true => values.push(projected.join(" ")),
+                false => values.push(projected.into_iter().next().unwrap_or_default()),
+            }
+        }
+
+        write!(writer, "{}", projection::join(&values, &self.settings.format))
+            .context("Unable to write to output-file")?;
+        writer.write_all(terminator).context("Unable to write to output-file")
+    }
+
+    // writes the aggregated values of all groups as TSV or CSV table with header
+    fn write_table(&self, writer: &mut dyn Write, aggregation: &Aggregation) -> Result<(), Error> {
+        for row in std::iter::once(aggregation.header()).chain(aggregation.rows()) {
+            writeln!(writer, "{}", projection::join(&row, &self.settings.format))
+                .context("Unable to write to output-file")?;
+        }
+
+        Ok(())
+    }
+
+    // writes a group separator between non-adjacent lines if context lines are requested, the line is followed by its
+    // own terminator and the group separator by the record separator
+    #[allow(clippy::too_many_arguments)]
+    fn write_line(
+        &self,
+        writer: &mut dyn Write,
+        number: usize,
+        prefix: &str,
+        line: &str,
+        terminator: &[u8],
+        separator: &[u8],
+        last_written: &mut Option<usize>,
+    ) -> Result<(), Error> {
+        let context = self.settings.before_context > 0 || self.settings.after_context > 0;
+        if context && last_written.is_some_and(|last_written| last_written + 1 < number) {
+            writer.write_all(b"--").context("Unable to write to output-file")?;
+            writer.write_all(separator).context("Unable to write to output-file")?;
+        }
+
+        writer
+            .write_all(prefix.as_bytes())
+            .context("Unable to write to output-file")?;
+        writer
+            .write_all(line.as_bytes())
+            .context("Unable to write to output-file")?;
+        writer.write_all(terminator).context("Unable to write to output-file")?;
+        *last_written = Some(number);
+
+        Ok(())
+    }
+
+    fn output_line(&self, tokens: Vec<Token>, matches: &Option<HashSet<Position>>) -> Option<String> {
+        match (&self.settings.mode, matches) {
+            (Mode::Filter, None) => None,
+            (Mode::Filter, Some(_)) => Some(self.normal_text(tokens)),
+            (Mode::Highlight(_), None) => Some(self.normal_text(tokens)),
+            (Mode::Highlight(colour), Some(matches)) => Some(self.highlighted_text(tokens, matches, *colour)),
+            (Mode::FilterHighlight(_), None) => None,
+            (Mode::FilterHighlight(colour), Some(matches)) => Some(self.highlighted_text(tokens, matches, *colour)),
+            (Mode::Reject, None) => Some(self.normal_text(tokens)),
+            (Mode::Reject, Some(_)) => None,
+            (Mode::OnlyMatching, _) => None,
         }
     }
 
@@ -141,15 +664,63 @@ impl<'a> Filter<'a> {
         tokens.into_iter().map(|t| t.word.to_string()).collect()
     }
 
+    // consecutive matched tokens of a value spanning multiple tokens are highlighted as one unit
     fn highlighted_text(&self, tokens: Vec<Token>, matches: &HashSet<Position>, colour: Colour) -> String {
-        tokens
-            .into_iter()
-            .map(|t| match matches.contains(&t.position) {
-                true => colour.paint(t.word).to_string(),
-                false => t.word.to_string(),
-            })
-            .collect()
+        let mut text = String::new();
+        let mut unit = String::new();
+        for token in tokens {
+            if matches.contains(&token.position) {
+                unit.push_str(token.word);
+                continue;
+            }
+            if !unit.is_empty() {
+                text.push_str(&colour.paint(unit.as_str()).to_string());
+                unit.clear();
+            }
+            text.push_str(token.word);
+        }
+        if !unit.is_empty() {
+            text.push_str(&colour.paint(unit.as_str()).to_string());
+        }
+
+        return text;
+    }
+}
+
+// a matched value with the field and byte offset within the line of its first token
+struct Unit {
+    position: Position,
+    field: usize,
+    start: usize,
+    text: String,
+}
+
+// groups consecutive matched tokens into units, separators are only matched within values spanning multiple tokens
+fn units(tokens: &[Token], matches: &HashSet<Position>) -> Vec<Unit> {
+    let mut units: Vec<Unit> = Vec::new();
+    let mut field = 0;
+    let mut start = 0;
+    let mut continued = false;
+    for token in tokens {
+        if !token.separator {
+            field += 1;
+        }
+        let matched = matches.contains(&token.position);
+        match units.last_mut() {
+            Some(unit) if matched && continued => unit.text.push_str(token.word),
+            _ if matched && !token.separator => units.push(Unit {
+                position: token.position,
+                field: field,
+                start: start,
+                text: token.word.to_string(),
+            }),
+            _ => {}
+        }
+        continued = matched && (continued || !token.separator);
+        start += token.word.len();
     }
+
+    return units;
 }
 
 #[cfg(test)]
@@ -158,10 +729,34 @@ pub mod test_utils {
 
     pub fn default_formats() -> Formats {
         Formats {
-            date: String::from(DATE_FORMAT),
-            time: String::from(TIME_FORMAT),
-            date_time: String::from(DATE_TIME_FORMAT),
-            local_date_time: String::from(LOCAL_DATE_TIME_FORMAT),
+            date: vec![String::from(DATE_FORMAT)],
+            time: vec![String::from(TIME_FORMAT)],
+            date_time: vec![String::from(DATE_TIME_FORMAT)],
+            local_date_time: vec![String::from(LOCAL_DATE_TIME_FORMAT)],
+            now: DateTime::parse_from_rfc3339("2021-10-16T21:41:07+02:00").unwrap(),
+        }
+    }
+
+    pub fn default_settings() -> Settings {
+        Settings {
+            formats: default_formats(),
+            mode: Mode::Filter,
+            count: false,
+            before_context: 0,
+            after_context: 0,
+            line_number: false,
+            byte_offset: false,
+            delimiter: None,
+            format: Format::Text,
+            print: None,
+            print_all: false,
+            aggregate: None,
+            follow: false,
+            count_interval: None,
+            decompress: true,
+            encoding: encoding_rs::UTF_8,
+            binary_files: BinaryFiles::Skip,
+            record_separator: RecordSeparator::Newline,
         }
     }
 }
@@ -169,7 +764,7 @@ pub mod test_utils {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tokenizer::Separators;
+    use crate::tokenizer::{Separators, WHITESPACES};
     use file_diff::diff_files;
     use tempfile::NamedTempFile;
 
@@ -186,14 +781,13 @@ mod tests {
         let tokenizer = Tokenizer::new(separators).unwrap();
         let expression = "$integer == 9";
         let settings = Settings {
-            formats: test_utils::default_formats(),
             mode: Mode::Filter,
-            count: false,
+            ..test_utils::default_settings()
         };
         let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
 
         // exercise
-        let lines = filter.filter(&mut input, &mut output).unwrap();
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
 
         // verify
         let mut input = input_file.reopen().unwrap();
@@ -224,14 +818,13 @@ mod tests {
         let tokenizer = Tokenizer::new(separators).unwrap();
         let expression = "$id == ipsum";
         let settings = Settings {
-            formats: test_utils::default_formats(),
             mode: Mode::Highlight(colour),
-            count: false,
+            ..test_utils::default_settings()
         };
         let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
 
         // exercise
-        let lines = filter.filter(&mut input, &mut output).unwrap();
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
 
         // verify
         let mut expected = expected_file.reopen().unwrap();
@@ -256,14 +849,13 @@ mod tests {
         let tokenizer = Tokenizer::new(separators).unwrap();
         let expression = "$id == abc";
         let settings = Settings {
-            formats: test_utils::default_formats(),
             mode: Mode::Highlight(Colour::Red),
-            count: false,
+            ..test_utils::default_settings()
         };
         let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
 
         // exercise
-        let lines = filter.filter(&mut input, &mut output).unwrap();
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
 
         // verify
         let mut input = input_file.reopen().unwrap();
@@ -288,14 +880,13 @@ mod tests {
         let tokenizer = Tokenizer::new(separators).unwrap();
         let expression = "$id == ipsum";
         let settings = Settings {
-            formats: test_utils::default_formats(),
             mode: Mode::Filter,
-            count: false,
+            ..test_utils::default_settings()
         };
         let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
 
         // exercise
-        let lines = filter.filter(&mut input, &mut output).unwrap();
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
 
         // verify
         let mut input = input_file.reopen().unwrap();
@@ -320,14 +911,13 @@ mod tests {
         let tokenizer = Tokenizer::new(separators).unwrap();
         let expression = "$id == abc";
         let settings = Settings {
-            formats: test_utils::default_formats(),
             mode: Mode::Filter,
-            count: false,
+            ..test_utils::default_settings()
         };
         let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
 
         // exercise
-        let lines = filter.filter(&mut input, &mut output).unwrap();
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
 
         // verify
         let mut input = input_file.reopen().unwrap();
@@ -358,14 +948,13 @@ mod tests {
         let tokenizer = Tokenizer::new(separators).unwrap();
         let expression = "$id == ipsum";
         let settings = Settings {
-            formats: test_utils::default_formats(),
             mode: Mode::FilterHighlight(colour),
-            count: false,
+            ..test_utils::default_settings()
         };
         let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
 
         // exercise
-        let lines = filter.filter(&mut input, &mut output).unwrap();
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
 
         // verify
         let mut expected = expected_file.reopen().unwrap();
@@ -390,14 +979,13 @@ mod tests {
         let tokenizer = Tokenizer::new(separators).unwrap();
         let expression = "$id == abc";
         let settings = Settings {
-            formats: test_utils::default_formats(),
             mode: Mode::FilterHighlight(Colour::Red),
-            count: false,
+            ..test_utils::default_settings()
         };
         let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
 
         // exercise
-        let lines = filter.filter(&mut input, &mut output).unwrap();
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
 
         // verify
         let mut input = input_file.reopen().unwrap();
@@ -406,4 +994,931 @@ mod tests {
         assert_eq!(0, lines.matched);
         assert!(!diff_files(&mut input, &mut output));
     }
+
+    #[test]
+    fn filter_context() {
+        // setup
+        let input_text = "a 1\nb 2\nc 9\nd 3\ne 4\nf 5\ng 6\nh 9\ni 7";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "b 2\nc 9\nd 3\n--\ng 6\nh 9\ni 7";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::Filter,
+            before_context: 1,
+            after_context: 1,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(9, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn filter_overlapping_context() {
+        // setup
+        let input_text = "a 1\nb 2\nc 9\nd 3\ne 9\nf 5\ng 6\nh 7\ni 9";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "a 1\nb 2\nc 9\nd 3\ne 9\nf 5\ng 6\nh 7\ni 9";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::Filter,
+            before_context: 2,
+            after_context: 2,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(9, lines.processed);
+        assert_eq!(3, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn filter_highlight_context() {
+        // setup
+        let colour = Colour::Red;
+
+        let input_text = "a 1\nb 2\nc 9\nd 3\ne 4\nf 9";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = format!("b 2\nc {}\n--\ne 4\nf {}", colour.paint("9"), colour.paint("9"));
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::FilterHighlight(colour),
+            before_context: 1,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(6, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn reject_matched() {
+        // setup
+        let input_text = "lorem ipsum dolor sit amet consectetuer";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$id == ipsum";
+        let settings = Settings {
+            mode: Mode::Reject,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut input = input_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(1, lines.processed);
+        assert_eq!(1, lines.matched);
+        assert_eq!(0, lines.rejected);
+        assert!(!diff_files(&mut input, &mut output));
+    }
+
+    #[test]
+    fn reject_unmatched() {
+        // setup
+        let input_text = "lorem ipsum dolor sit amet consectetuer";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$id == abc";
+        let settings = Settings {
+            mode: Mode::Reject,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut input = input_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(1, lines.processed);
+        assert_eq!(0, lines.matched);
+        assert_eq!(1, lines.rejected);
+        assert!(diff_files(&mut input, &mut output));
+    }
+
+    #[test]
+    fn reject_context() {
+        // setup
+        let input_text = "a 9\nb 9\nc 1\nd 9\ne 9\nf 9\ng 2";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "b 9\nc 1\nd 9\n--\nf 9\ng 2";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::Reject,
+            before_context: 1,
+            after_context: 1,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(7, lines.processed);
+        assert_eq!(5, lines.matched);
+        assert_eq!(2, lines.rejected);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn filter_inputs() {
+        // setup
+        let mut first_file = NamedTempFile::new().unwrap();
+        writeln!(first_file, "a 9\nb 1").unwrap();
+        let mut second_file = NamedTempFile::new().unwrap();
+        writeln!(second_file, "c 1\nd 9").unwrap();
+        let first = first_file.path().to_path_buf();
+        let second = second_file.path().to_path_buf();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = format!(
+            "{}:1:a 9\n{}-2-b 1\n{}-1-c 1\n{}:2:d 9",
+            first.display(),
+            first.display(),
+            second.display(),
+            second.display()
+        );
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::Filter,
+            before_context: 1,
+            after_context: 1,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+        let inputs = vec![Ok(Input::File(first)), Ok(Input::File(second))];
+
+        // exercise
+        let lines = filter.filter_inputs(&inputs, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(4, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert_eq!(2, lines.rejected);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn filter_inputs_unreadable() {
+        // setup
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "a 9\nb 1").unwrap();
+        let input = input_file.path().to_path_buf();
+        let missing = input.with_extension("missing");
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = format!("{}:1:a 9", input.display());
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::Filter,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+        let inputs = vec![
+            Ok(Input::File(missing)),
+            Err(anyhow!("Input-file 'logs' is a directory")),
+            Ok(Input::File(input)),
+        ];
+
+        // exercise
+        let lines = filter.filter_inputs(&inputs, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(2, lines.processed);
+        assert_eq!(1, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn filter_inputs_corrupt() {
+        // setup
+        let mut first_file = NamedTempFile::new().unwrap();
+        writeln!(first_file, "a 9\nb 1").unwrap();
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(b"c 9\nd 1\n").unwrap();
+        let mut truncated = gzip.finish().unwrap();
+        truncated.truncate(12);
+        let mut corrupt_file = NamedTempFile::new().unwrap();
+        corrupt_file.write_all(&truncated).unwrap();
+        let mut second_file = NamedTempFile::new().unwrap();
+        writeln!(second_file, "e 9\nf 1").unwrap();
+        let first = first_file.path().to_path_buf();
+        let second = second_file.path().to_path_buf();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = format!("{}:1:a 9\n{}:1:e 9", first.display(), second.display());
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::Filter,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+        let inputs = vec![
+            Ok(Input::File(first)),
+            Ok(Input::File(corrupt_file.path().to_path_buf())),
+            Ok(Input::File(second)),
+        ];
+
+        // exercise
+        let lines = filter.filter_inputs(&inputs, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn line_number_byte_offset() {
+        // setup
+        let input_text = "a 9\r\nb 1\nc 9";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "1:0:a 9\n2-5-b 1\n3:9:c 9";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::Filter,
+            after_context: 1,
+            line_number: true,
+            byte_offset: true,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(3, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn only_matching() {
+        // setup
+        let input_text = "a 9 b 7 c 9\nd 1\ne 9";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "1:2:2:9\n1:10:6:9\n3:18:2:9";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::OnlyMatching,
+            before_context: 1,
+            line_number: true,
+            byte_offset: true,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(3, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn only_matching_delimiter() {
+        // setup
+        let input_text = "a 9 b 7 c 9\nd 1\ne 9";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "9,9\n9";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::OnlyMatching,
+            delimiter: Some(String::from(",")),
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(3, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn ndjson() {
+        // setup
+        let input_text = "tcp 10.0.0.1:22 9\nudp 10.0.0.1:53 9";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = concat!(
+            r#"{"line":1,"offset":0,"text":"tcp 10.0.0.1:22 9","matches":["#,
+            r#"{"position":2,"start":4,"end":15,"token":"10.0.0.1:22","type":"$ipv4SocketAddress"},"#,
+            r#"{"position":3,"start":16,"end":17,"token":"9","type":"$integer"}]}"#
+        );
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "port($ipv4SocketAddress) == 22 and $integer@3 == 9";
+        let settings = Settings {
+            format: Format::Ndjson,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(2, lines.processed);
+        assert_eq!(1, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn json() {
+        // setup
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "a 9\nb 1\nc 9").unwrap();
+        let input = input_file.path().to_path_buf();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = concat!(
+            "[\n",
+            r#"{"line":1,"offset":0,"text":"a 9","matches":[{"position":2,"start":2,"end":3,"token":"9","type":"$integer"}]},"#,
+            "\n",
+            r#"{"line":3,"offset":8,"text":"c 9","matches":[{"position":2,"start":2,"end":3,"token":"9","type":"$integer"}]}"#,
+            "\n]"
+        );
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            format: Format::Json,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+        let inputs = vec![Ok(Input::File(input))];
+
+        // exercise
+        let lines = filter.filter_inputs(&inputs, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(3, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn print_tsv() {
+        // setup
+        let input_text = "tcp 10.0.0.1:22 9 7\nudp 1\ntcp 10.0.0.2:80";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "tcp\t22\t9\ntcp\t80\t";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$1 == tcp";
+        let settings = Settings {
+            format: Format::Tsv,
+            print: Some(String::from("$1, port($ipv4SocketAddress), $integer")),
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(3, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn print_all_csv() {
+        // setup
+        let input_text = "a 9 7\nb 1\nc \"d\"";
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "{}", input_text).unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "1,9 7,a 9 7\n3,,\"c \"\"d\"\"\"";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9 or $1 == c";
+        let settings = Settings {
+            format: Format::Csv,
+            print: Some(String::from("$integer,$text")),
+            print_all: true,
+            line_number: true,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(3, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn aggregate() {
+        // setup
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "a 10.0.0.1 9\nb 10.0.0.2 1\nc 10.0.0.1 3").unwrap();
+        let input = input_file.path().to_path_buf();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "$ipv4Address,count(),sum($integer)\n10.0.0.1,2,12";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer > 1";
+        let settings = Settings {
+            format: Format::Csv,
+            aggregate: Some(String::from("count(), sum($integer) by $ipv4Address")),
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+        let inputs = vec![Ok(Input::File(input))];
+
+        // exercise
+        let lines = filter.filter_inputs(&inputs, &mut output).unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(3, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn follow() {
+        // setup
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(input_file, "a 9\nb 1").unwrap();
+        let input = input_file.path().to_path_buf();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let expected_text = "1:a 9\n3:c 9\n4:d 9";
+        let mut expected_file = NamedTempFile::new().unwrap();
+        writeln!(expected_file, "{}", expected_text).unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            mode: Mode::Filter,
+            line_number: true,
+            follow: true,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut read = Follow::new(&input, Duration::from_millis(5), Arc::clone(&stop), Box::new(|| {})).unwrap();
+        let appender_stop = Arc::clone(&stop);
+        let appender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            writeln!(input_file, "c 9").unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            writeln!(input_file, "d 9").unwrap();
+            appender_stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut read, &mut output).unwrap();
+        appender.join().unwrap();
+
+        // verify
+        let mut expected = expected_file.reopen().unwrap();
+        let mut output = output_file.reopen().unwrap();
+        assert_eq!(4, lines.processed);
+        assert_eq!(3, lines.matched);
+        assert!(diff_files(&mut expected, &mut output));
+    }
+
+    #[test]
+    fn binary_files() {
+        // setup
+        let input_bytes = b"a 9\nb \xff 9\nc\x00 9\nd 1\n";
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+
+        let policies = vec![
+            (BinaryFiles::Skip, Some("Binary file matches\n")),
+            (BinaryFiles::Lossy, Some("a 9\nb \u{fffd} 9\nc\u{0} 9\n")),
+            (BinaryFiles::Fail, None),
+        ];
+        for (binary_files, expected_text) in policies {
+            let mut input_file = NamedTempFile::new().unwrap();
+            input_file.write_all(input_bytes).unwrap();
+            let mut input = input_file.reopen().unwrap();
+
+            let output_file = NamedTempFile::new().unwrap();
+            let mut output = output_file.reopen().unwrap();
+
+            let settings = Settings {
+                binary_files: binary_files,
+                ..test_utils::default_settings()
+            };
+            let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+            // exercise
+            let lines = filter.filter_lines(None, &mut input, &mut output);
+
+            // verify
+            let mut output_text = String::new();
+            output_file.reopen().unwrap().read_to_string(&mut output_text).unwrap();
+            match expected_text {
+                Some(expected_text) => {
+                    let lines = lines.unwrap();
+                    assert_eq!(4, lines.processed);
+                    assert_eq!(3, lines.matched);
+                    assert_eq!(expected_text, output_text);
+                }
+                None => {
+                    assert_eq!(
+                        "Line '1' of input-file is binary or invalid UTF-8",
+                        lines.err().unwrap().to_string()
+                    );
+                    assert_eq!("a 9\n", output_text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn binary_files_after_matching_line() {
+        // setup
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+
+        let inputs = vec![
+            (
+                RecordSeparator::Newline,
+                &b"a 9\nb 1\nc\x00 1\n"[..],
+                "Binary file matches\n",
+            ),
+            (
+                RecordSeparator::Newline,
+                &b"a 9\nb 1\nc \xe9 1\n"[..],
+                "Binary file matches\n",
+            ),
+            (RecordSeparator::Null, &b"a 9\x00b 1\x00"[..], "a 9\x00"),
+        ];
+        for (record_separator, input_bytes, expected_text) in inputs {
+            let mut input_file = NamedTempFile::new().unwrap();
+            input_file.write_all(input_bytes).unwrap();
+            let mut input = input_file.reopen().unwrap();
+
+            let output_file = NamedTempFile::new().unwrap();
+            let mut output = output_file.reopen().unwrap();
+
+            let settings = Settings {
+                record_separator: record_separator,
+                ..test_utils::default_settings()
+            };
+            let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+            // exercise
+            let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+            // verify
+            let mut output_text = String::new();
+            output_file.reopen().unwrap().read_to_string(&mut output_text).unwrap();
+            assert_eq!(1, lines.matched);
+            assert_eq!(expected_text, output_text);
+        }
+    }
+
+    #[test]
+    fn encoding() {
+        // setup
+        let mut input_file = NamedTempFile::new().unwrap();
+        input_file.write_all(b"caf\xe9 9\nna\xefve 1\n").unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let separators = Separators::new(vec![" "]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            encoding: Encoding::for_label(b"latin1").unwrap(),
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut output_text = String::new();
+        output_file.reopen().unwrap().read_to_string(&mut output_text).unwrap();
+        assert_eq!(2, lines.processed);
+        assert_eq!(1, lines.matched);
+        assert_eq!("caf\u{e9} 9\n", output_text);
+    }
+
+    #[test]
+    fn null_data() {
+        // setup
+        let mut input_file = NamedTempFile::new().unwrap();
+        input_file.write_all(b"a 9\0b\n1\0c\n9").unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let separators = Separators::new(vec![WHITESPACES]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            line_number: true,
+            record_separator: RecordSeparator::Null,
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut output_text = String::new();
+        output_file.reopen().unwrap().read_to_string(&mut output_text).unwrap();
+        assert_eq!(3, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert_eq!("1:a 9\u{0}3:c\n9", output_text);
+    }
+
+    #[test]
+    fn record_separator() {
+        // setup
+        let mut input_file = NamedTempFile::new().unwrap();
+        input_file.write_all(b"a 9;;b 1;;c 9").unwrap();
+        let mut input = input_file.reopen().unwrap();
+
+        let output_file = NamedTempFile::new().unwrap();
+        let mut output = output_file.reopen().unwrap();
+
+        let separators = Separators::new(vec![WHITESPACES]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = "$integer == 9";
+        let settings = Settings {
+            record_separator: RecordSeparator::Pattern(regex::bytes::Regex::new(";;").unwrap()),
+            ..test_utils::default_settings()
+        };
+        let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+        // exercise
+        let lines = filter.filter_lines(None, &mut input, &mut output).unwrap();
+
+        // verify
+        let mut output_text = String::new();
+        output_file.reopen().unwrap().read_to_string(&mut output_text).unwrap();
+        assert_eq!(3, lines.processed);
+        assert_eq!(2, lines.matched);
+        assert_eq!("a 9;;c 9", output_text);
+    }
+
+    #[test]
+    fn joined_tokens() {
+        // setup
+        let input_text = "Oct 16 21:41:07 host a\nOct 16 20:41:07 host b\n";
+        let separators = Separators::new(vec![WHITESPACES, ":"]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let expression = r#"$time > "Oct 16 21:00:00""#;
+        let modes = vec![
+            (
+                Mode::FilterHighlight(Colour::Red),
+                "\u{1b}[31mOct 16 21:41:07\u{1b}[0m host a\n",
+            ),
+            (Mode::OnlyMatching, "Oct 16 21:41:07\n"),
+        ];
+        for (mode, expected_text) in modes {
+            let output_file = NamedTempFile::new().unwrap();
+            let mut output = output_file.reopen().unwrap();
+
+            let settings = Settings {
+                formats: Formats {
+                    time: vec![String::from("%b %d %H:%M:%S")],
+                    ..test_utils::default_formats()
+                },
+                mode: mode,
+                ..test_utils::default_settings()
+            };
+            let filter = Filter::new(&tokenizer, expression, &settings).unwrap();
+
+            // exercise
+            let lines = filter
+                .filter_lines(None, &mut input_text.as_bytes(), &mut output)
+                .unwrap();
+
+            // verify
+            let mut output_text = String::new();
+            output_file.reopen().unwrap().read_to_string(&mut output_text).unwrap();
+            assert_eq!(2, lines.processed);
+            assert_eq!(1, lines.matched);
+            assert_eq!(expected_text, output_text);
+        }
+    }
 }
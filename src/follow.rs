@@ -0,0 +1,229 @@
+use anyhow::{Context, Error};
+use std::fs::{metadata, File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// reads a file like 'tail -F'; at the end of the file it waits for appended data, re-opens the file if it was
+// rotated (inode changed), and starts from the beginning if it was truncated; it ends only once stopped
+pub struct Follow<'a> {
+    path: PathBuf,
+    file: File,
+    inode: u64,
+    offset: u64,
+    poll_interval: Duration,
+    stop: Arc<AtomicBool>,
+    tick: Box<dyn FnMut() + 'a>,
+}
+
+impl<'a> Follow<'a> {
+    pub fn new(
+        path: &Path,
+        poll_interval: Duration,
+        stop: Arc<AtomicBool>,
+        tick: Box<dyn FnMut() + 'a>,
+    ) -> Result<Self, Error> {
+        let file = File::open(path).context(format!("Failed to open input-file '{}'", path.display()))?;
+        let inode = inode(
+            &file
+                .metadata()
+                .context(format!("Failed to read input-file '{}'", path.display()))?,
+        );
+
+        Ok(Follow {
+            path: path.to_path_buf(),
+            file,
+            inode,
+            offset: 0,
+            poll_interval,
+            stop,
+            tick,
+        })
+    }
+
+    // returns true if the file was rotated or truncated and reading should continue from its beginning
+    fn reopen(&mut self) -> std::io::Result<bool> {
+        let metadata = match metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(_) => return Ok(false), // rotated but not re-created yet
+        };
+
+        if inode(&metadata) != self.inode {
+            self.file = File::open(&self.path)?;
+            self.inode = inode(&self.file.metadata()?);
+            self.offset = 0;
+            return Ok(true);
+        }
+
+        if metadata.len() < self.offset {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.offset = 0;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+impl<'a> Read for Follow<'a> {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            (self.tick)();
+
+            // stop is checked before reading so that data appended before stopping is not lost
+            let stopped = self.stop.load(Ordering::Relaxed);
+            let length = self.file.read(buffer)?;
+            if length > 0 {
+                self.offset += length as u64;
+                return Ok(length);
+            }
+
+            if self.reopen()? {
+                continue;
+            }
+
+            if stopped {
+                return Ok(0);
+            }
+
+            sleep(self.poll_interval);
+        }
+    }
+}
+
+// decides when a summary is due, either on request (SIGUSR1 on Unix) or periodically
+pub struct Report {
+    requested: Arc<AtomicBool>,
+    interval: Option<Duration>,
+    last: Instant,
+}
+
+impl Report {
+    pub fn new(interval: Option<Duration>) -> Result<Self, Error> {
+        let requested = Arc::new(AtomicBool::new(false));
+        register_request(&requested)?;
+
+        Ok(Report {
+            requested,
+            interval,
+            last: Instant::now(),
+        })
+    }
+
+    pub fn due(&mut self) -> bool {
+        let requested = self.requested.swap(false, Ordering::Relaxed);
+        let elapsed = self.interval.is_some_and(|interval| self.last.elapsed() >= interval);
+        if requested || elapsed {
+            self.last = Instant::now();
+        }
+
+        requested || elapsed
+    }
+}
+
+//
+// Windows specific code
+//
+
+#[cfg(target_family = "windows")]
+fn inode(_: &Metadata) -> u64 {
+    // rotation is detected only through truncation on Windows
+    0
+}
+
+#[cfg(target_family = "windows")]
+fn register_request(_: &Arc<AtomicBool>) -> Result<(), Error> {
+    // summaries are printed only periodically on Windows
+    Ok(())
+}
+
+//
+// Unix specific code
+//
+
+#[cfg(target_family = "unix")]
+fn inode(metadata: &Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::ino(metadata)
+}
+
+#[cfg(target_family = "unix")]
+fn register_request(requested: &Arc<AtomicBool>) -> Result<(), Error> {
+    signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(requested))
+        .context("Failed to register SIGUSR1 handler")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod follow_tests {
+    use super::*;
+    use std::fs::{remove_file, rename, OpenOptions};
+    use std::io::{BufRead, BufReader, Write};
+    use std::thread;
+    use tempfile::tempdir;
+
+    #[test]
+    fn follow_appended_truncated_and_rotated() {
+        // setup
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("follow.log");
+        writeln!(File::create(&path).unwrap(), "a").unwrap();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let follow = Follow::new(&path, Duration::from_millis(5), Arc::clone(&stop), Box::new(|| {})).unwrap();
+        let mut reader = BufReader::new(follow);
+
+        // exercise & verify
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!("a\n", line);
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "b").unwrap();
+        let writer = thread::spawn(move || {
+            sleep(Duration::from_millis(50));
+            writeln!(file, "c").unwrap();
+        });
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!("bc\n", line);
+        writer.join().unwrap();
+
+        File::create(&path).unwrap();
+        writeln!(OpenOptions::new().append(true).open(&path).unwrap(), "d").unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!("d\n", line);
+
+        rename(&path, directory.path().join("follow.log.1")).unwrap();
+        write!(File::create(&path).unwrap(), "e\nf\n").unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!("e\n", line);
+
+        remove_file(directory.path().join("follow.log.1")).unwrap();
+        stop.store(true, Ordering::Relaxed);
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!("f\n", rest);
+    }
+
+    #[test]
+    fn report_interval() {
+        // setup
+        let mut report = Report::new(Some(Duration::from_millis(20))).unwrap();
+        let mut unreported = Report::new(None).unwrap();
+
+        // exercise & verify
+        assert!(!report.due());
+        sleep(Duration::from_millis(30));
+        assert!(report.due());
+        assert!(!report.due());
+        assert!(!unreported.due());
+    }
+}
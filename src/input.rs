@@ -0,0 +1,324 @@
+use anyhow::{anyhow, Context, Error};
+use bzip2::bufread::MultiBzDecoder;
+use flate2::bufread::MultiGzDecoder;
+use std::fs::{read_dir, File};
+use std::io::{self, stdin, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+pub const STDIN: &str = "-";
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Input {
+    pub fn name(&self) -> String {
+        match self {
+            Self::Stdin => String::from("(standard input)"),
+            Self::File(path) => path.display().to_string(),
+        }
+    }
+
+    pub fn open(&self, decompress: bool) -> Result<Box<dyn Read>, Error> {
+        let read: Box<dyn Read> = match self {
+            Self::Stdin => Box::new(stdin()),
+            Self::File(path) => {
+                Box::new(File::open(path).context(format!("Failed to open input-file '{}'", path.display()))?)
+            }
+        };
+
+        match decompress {
+            true => Input::decompress(read).context(format!("Failed to read input-file '{}'", self.name())),
+            false => Ok(read),
+        }
+    }
+
+    // detects gzip, zstd, bzip2, and xz compressed input by its magic bytes; corrupt or truncated streams are detected
+    // when opened as far as the first decompressed buffer reaches, later failures are reported while reading
+    fn decompress(read: Box<dyn Read>) -> Result<Box<dyn Read>, Error> {
+        let mut reader = BufReader::new(read);
+        let magic = reader.fill_buf()?;
+        let compression = if magic.starts_with(GZIP_MAGIC) {
+            "gzip"
+        } else if magic.starts_with(ZSTD_MAGIC) {
+            "zstd"
+        } else if magic.starts_with(BZIP2_MAGIC) {
+            "bzip2"
+        } else if magic.starts_with(XZ_MAGIC) {
+            "xz"
+        } else {
+            return Ok(Box::new(reader));
+        };
+
+        let decoder: Box<dyn Read> = match compression {
+            "gzip" => Box::new(MultiGzDecoder::new(reader)),
+            "zstd" => Box::new(zstd::Decoder::with_buffer(reader)?),
+            "bzip2" => Box::new(MultiBzDecoder::new(reader)),
+            _ => Box::new(xz2::bufread::XzDecoder::new_multi_decoder(reader)),
+        };
+        let mut decoder = Decompressed {
+            reader: BufReader::new(decoder),
+            compression,
+        };
+        decoder
+            .reader
+            .fill_buf()
+            .context(format!("Corrupt or truncated {} stream", compression))?;
+
+        Ok(Box::new(decoder))
+    }
+}
+
+// names the compression in errors of the decoder
+struct Decompressed {
+    reader: BufReader<Box<dyn Read>>,
+    compression: &'static str,
+}
+
+impl Read for Decompressed {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buffer).map_err(|error| {
+            io::Error::new(
+                error.kind(),
+                format!("Corrupt or truncated {} stream: {}", self.compression, error),
+            )
+        })
+    }
+}
+
+pub struct Inputs {}
+
+impl Inputs {
+    // resolves files, directories, and glob patterns; inputs which can not be resolved are returned as errors
+    pub fn resolve(patterns: Vec<&str>, recursive: bool) -> Vec<Result<Input, Error>> {
+        if patterns.is_empty() {
+            return vec![Ok(Input::Stdin)];
+        }
+
+        let mut inputs = Vec::new();
+        for pattern in patterns {
+            if pattern == STDIN {
+                inputs.push(Ok(Input::Stdin));
+            } else if pattern.contains(['*', '?', '[']) {
+                Inputs::resolve_glob(pattern, recursive, &mut inputs);
+            } else {
+                Inputs::resolve_path(Path::new(pattern), recursive, &mut inputs);
+            }
+        }
+
+        inputs
+    }
+
+    fn resolve_glob(pattern: &str, recursive: bool, inputs: &mut Vec<Result<Input, Error>>) {
+        let paths = match glob::glob(pattern) {
+            Ok(paths) => paths,
+            Err(error) => {
+                inputs.push(Err(anyhow!("Invalid glob pattern '{}': {}", pattern, error)));
+                return;
+            }
+        };
+
+        let mut matched = false;
+        for path in paths {
+            matched = true;
+            match path {
+                Ok(path) => Inputs::resolve_path(&path, recursive, inputs),
+                Err(error) => inputs.push(Err(anyhow!("Failed to read '{}'", error.path().display()))),
+            }
+        }
+
+        if !matched {
+            inputs.push(Err(anyhow!("No input-file matches glob pattern '{}'", pattern)));
+        }
+    }
+
+    fn resolve_path(path: &Path, recursive: bool, inputs: &mut Vec<Result<Input, Error>>) {
+        if !path.is_dir() {
+            inputs.push(Ok(Input::File(path.to_path_buf())));
+            return;
+        }
+
+        if !recursive {
+            inputs.push(Err(anyhow!("Input-file '{}' is a directory", path.display())));
+            return;
+        }
+
+        let entries = match read_dir(path) {
+            Ok(entries) => entries,
+            Err(error) => {
+                inputs.push(Err(anyhow!("Failed to read directory '{}': {}", path.display(), error)));
+                return;
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect();
+        paths.sort();
+        for path in paths {
+            Inputs::resolve_path(&path, recursive, inputs);
+        }
+    }
+}
+
+#[cfg(test)]
+mod inputs_tests {
+    use super::*;
+    use std::fs::create_dir;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn stdin() {
+        // exercise
+        let inputs = Inputs::resolve(vec![], false);
+        let dash_inputs = Inputs::resolve(vec![STDIN], false);
+
+        // verify
+        assert_eq!(names(&inputs), vec!["(standard input)"]);
+        assert_eq!(names(&dash_inputs), vec!["(standard input)"]);
+    }
+
+    #[test]
+    fn files_and_directories() {
+        // setup
+        let directory = tempdir().unwrap();
+        let root = directory.path();
+        create_dir(root.join("logs")).unwrap();
+        File::create(root.join("a.log")).unwrap();
+        File::create(root.join("logs").join("c.log")).unwrap();
+        File::create(root.join("logs").join("b.log")).unwrap();
+
+        let a = root.join("a.log").display().to_string();
+        let logs = root.join("logs").display().to_string();
+
+        // exercise
+        let inputs = Inputs::resolve(vec![&a, &logs], false);
+        let recursive_inputs = Inputs::resolve(vec![&a, &logs], true);
+
+        // verify
+        assert_eq!(
+            names(&inputs),
+            vec![a.clone(), format!("Input-file '{}' is a directory", logs)]
+        );
+        assert_eq!(
+            names(&recursive_inputs),
+            vec![
+                a,
+                root.join("logs").join("b.log").display().to_string(),
+                root.join("logs").join("c.log").display().to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn glob_patterns() {
+        // setup
+        let directory = tempdir().unwrap();
+        let root = directory.path();
+        File::create(root.join("a.log")).unwrap();
+        File::create(root.join("b.log")).unwrap();
+        File::create(root.join("c.txt")).unwrap();
+
+        let pattern = root.join("*.log").display().to_string();
+        let unmatched_pattern = root.join("*.gz").display().to_string();
+
+        // exercise
+        let inputs = Inputs::resolve(vec![&pattern, &unmatched_pattern], false);
+
+        // verify
+        assert_eq!(
+            names(&inputs),
+            vec![
+                root.join("a.log").display().to_string(),
+                root.join("b.log").display().to_string(),
+                format!("No input-file matches glob pattern '{}'", unmatched_pattern)
+            ]
+        );
+    }
+
+    #[test]
+    fn decompressed() {
+        // setup
+        let text = "a 9\nb 1\n";
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(text.as_bytes()).unwrap();
+        let mut bzip2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bzip2.write_all(text.as_bytes()).unwrap();
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(text.as_bytes()).unwrap();
+
+        let directory = tempdir().unwrap();
+        let compressed = vec![
+            ("a.log", text.as_bytes().to_vec()),
+            ("a.log.gz", gzip.finish().unwrap()),
+            ("a.log.zst", zstd::encode_all(text.as_bytes(), 0).unwrap()),
+            ("a.log.bz2", bzip2.finish().unwrap()),
+            ("a.log.xz", xz.finish().unwrap()),
+        ];
+
+        for (name, bytes) in compressed {
+            let path = directory.path().join(name);
+            File::create(&path).unwrap().write_all(&bytes).unwrap();
+
+            // exercise
+            let mut decompressed = String::new();
+            Input::File(path.clone())
+                .open(true)
+                .unwrap()
+                .read_to_string(&mut decompressed)
+                .unwrap();
+            let mut raw = Vec::new();
+            Input::File(path).open(false).unwrap().read_to_end(&mut raw).unwrap();
+
+            // verify
+            assert_eq!(text, decompressed);
+            assert_eq!(bytes, raw);
+        }
+    }
+
+    #[test]
+    fn decompressed_corrupt() {
+        // setup
+        let corrupt_gzip = [GZIP_MAGIC, b"corrupt"].concat();
+        let corrupt_xz = [XZ_MAGIC, b"corrupt"].concat();
+
+        let directory = tempdir().unwrap();
+        let corrupt = vec![("a.log.gz", corrupt_gzip, "gzip"), ("a.log.xz", corrupt_xz, "xz")];
+
+        for (name, bytes, compression) in corrupt {
+            let path = directory.path().join(name);
+            File::create(&path).unwrap().write_all(&bytes).unwrap();
+
+            // exercise
+            let error = Input::File(path.clone()).open(true).err().unwrap();
+
+            // verify
+            assert_eq!(
+                format!("Failed to read input-file '{}'", path.display()),
+                error.to_string()
+            );
+            assert_eq!(
+                format!("Corrupt or truncated {} stream", compression),
+                error.chain().nth(1).unwrap().to_string()
+            );
+        }
+    }
+
+    fn names(inputs: &[Result<Input, Error>]) -> Vec<String> {
+        inputs
+            .iter()
+            .map(|input| match input {
+                Ok(input) => input.name(),
+                Err(error) => error.to_string(),
+            })
+            .collect()
+    }
+}
@@ -1,8 +1,25 @@
+mod aggregation;
 mod ansi;
+#[allow(clippy::needless_return, clippy::redundant_field_names)]
 mod arguments;
+mod diagnostics;
+mod evaluator;
+#[allow(clippy::needless_return, clippy::useless_vec)]
 mod expression;
+#[allow(clippy::needless_return, clippy::redundant_field_names)]
 mod filter;
+mod follow;
+mod input;
+#[allow(
+    clippy::needless_return,
+    clippy::redundant_field_names,
+    clippy::bool_assert_comparison,
+    clippy::get_first
+)]
 mod parser;
+mod projection;
+mod record;
+#[allow(clippy::needless_return, clippy::redundant_field_names)]
 mod tokenizer;
 
 use anyhow::{Context, Error};
@@ -19,7 +36,7 @@ fn main() -> Result<(), Error> {
     ansi::enable_ansi_support(&args.settings).context("Enabling ANSI support failed")?;
 
     filter
-        .filter(&mut args.input, &mut args.output)
+        .filter_inputs(&args.inputs, &mut args.output)
         .context("Filtering failed")?;
 
     Ok(())
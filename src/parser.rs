@@ -1,5 +1,6 @@
-use anyhow::Error;
-use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use anyhow::{anyhow, Error};
+use chrono::format::{parse, strftime::StrftimeItems, Item, ParseResult, Parsed};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use ipnet::{IpNet, Ipv4Net, Ipv6Net};
 use semver::{Version, VersionReq};
 use std::marker::PhantomData;
@@ -10,8 +11,24 @@ use crate::tokenizer::Token;
 
 pub type Id = String;
 
+// separators which may be joined with the tokens of a timestamp
+pub const TIMESTAMP_LITERALS: &str = " ,:/-.[]";
+
+const CLF_FORMAT: &str = "%d/%b/%Y:%H:%M:%S %z";
+const SYSLOG_FORMAT: &str = "%b %e %H:%M:%S";
+const NAMES: &[&str] = &[
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec", "Mon", "Tue", "Wed", "Thu",
+    "Fri", "Sat", "Sun",
+];
+const ISO_WEEK_FORMAT: &str = "%G-W%V-%u";
+
 pub trait FromWord<F>: Sized {
     fn from_word(word: &str, format: &F) -> Result<Self, Error>;
+
+    // rejects words which can not start a value before tokens are joined with them
+    fn starts(_word: &str, _format: &F) -> bool {
+        true
+    }
 }
 
 impl FromWord<()> for i64 {
@@ -38,28 +55,161 @@ impl FromWord<()> for Id {
     }
 }
 
-impl FromWord<String> for NaiveDate {
-    fn from_word(word: &str, format: &String) -> Result<Self, Error> {
-        NaiveDate::parse_from_str(word, format).map_err(|e| e.into())
+impl FromWord<Vec<String>> for NaiveDate {
+    fn from_word(word: &str, formats: &Vec<String>) -> Result<Self, Error> {
+        parse_formats(word, formats, NaiveDate::parse_from_str)
     }
 }
 
-impl FromWord<String> for NaiveTime {
-    fn from_word(word: &str, format: &String) -> Result<Self, Error> {
-        NaiveTime::parse_from_str(word, format).map_err(|e| e.into())
+impl FromWord<Vec<String>> for NaiveTime {
+    fn from_word(word: &str, formats: &Vec<String>) -> Result<Self, Error> {
+        parse_formats(word, formats, NaiveTime::parse_from_str)
     }
 }
 
-impl FromWord<String> for DateTime<FixedOffset> {
-    fn from_word(word: &str, format: &String) -> Result<Self, Error> {
-        DateTime::parse_from_str(word, format).map_err(|e| e.into())
+impl FromWord<Vec<String>> for DateTime<FixedOffset> {
+    fn from_word(word: &str, formats: &Vec<String>) -> Result<Self, Error> {
+        parse_formats(word, formats, DateTime::parse_from_str)
     }
 }
 
-impl FromWord<String> for NaiveDateTime {
-    fn from_word(word: &str, format: &String) -> Result<Self, Error> {
-        NaiveDateTime::parse_from_str(word, format).map_err(|e| e.into())
+impl FromWord<Vec<String>> for NaiveDateTime {
+    fn from_word(word: &str, formats: &Vec<String>) -> Result<Self, Error> {
+        parse_formats(word, formats, NaiveDateTime::parse_from_str)
+    }
+}
+
+// tries the formats in order and returns the error of the last format if none of them parses
+fn parse_formats<T>(word: &str, formats: &[String], parse: fn(&str, &str) -> ParseResult<T>) -> Result<T, Error> {
+    let mut result = Err(anyhow!("no format to parse '{}'", word));
+    for format in formats {
+        match parse(word, format) {
+            Ok(value) => return Ok(value),
+            Err(error) => result = Err(error.into()),
+        }
+    }
+
+    return result;
+}
+
+// recognizes RFC 3339 (and RFC 5424 syslog), RFC 2822, Apache/NCSA CLF, RFC 3164 syslog, and ISO week date timestamps,
+// and Unix epoch seconds and milliseconds; timestamps without offset are in UTC and the year of syslog timestamps is
+// inferred from now
+impl FromWord<DateTime<Utc>> for DateTime<Utc> {
+    fn from_word(word: &str, now: &DateTime<Utc>) -> Result<Self, Error> {
+        let word = word
+            .strip_prefix('[')
+            .and_then(|word| word.strip_suffix(']'))
+            .unwrap_or(word);
+        epoch(word)
+            .or_else(|| {
+                DateTime::parse_from_rfc3339(word)
+                    .ok()
+                    .map(|timestamp| timestamp.to_utc())
+            })
+            .or_else(|| {
+                DateTime::parse_from_rfc2822(word)
+                    .ok()
+                    .map(|timestamp| timestamp.to_utc())
+            })
+            .or_else(|| {
+                DateTime::parse_from_str(word, CLF_FORMAT)
+                    .ok()
+                    .map(|timestamp| timestamp.to_utc())
+            })
+            .or_else(|| syslog(word, *now))
+            .or_else(|| {
+                NaiveDate::parse_from_str(word, ISO_WEEK_FORMAT)
+                    .ok()
+                    .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+            })
+            .ok_or_else(|| anyhow!("invalid timestamp '{}'", word))
+    }
+
+    // timestamps start with a digit, a bracket, or a month or day name
+    fn starts(word: &str, _: &DateTime<Utc>) -> bool {
+        word.starts_with(|c: char| c.is_ascii_digit() || c == '[')
+            || NAMES
+                .iter()
+                .any(|name| word.get(..3).is_some_and(|start| start.eq_ignore_ascii_case(name)))
+    }
+}
+
+// seconds with an optional fraction have 10 digits, milliseconds have 13 digits
+fn epoch(word: &str) -> Option<DateTime<Utc>> {
+    let (seconds, fraction) = match word.split_once('.') {
+        Some((seconds, fraction)) => (seconds, Some(fraction)),
+        None => (word, None),
+    };
+    if !seconds.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    match (seconds.len(), fraction) {
+        (10, None) => DateTime::from_timestamp(seconds.parse().ok()?, 0),
+        (10, Some(fraction)) if (1..=9).contains(&fraction.len()) && fraction.chars().all(|c| c.is_ascii_digit()) => {
+            let nanoseconds = fraction.parse::<u32>().ok()? * 10u32.pow(9 - fraction.len() as u32);
+            DateTime::from_timestamp(seconds.parse().ok()?, nanoseconds)
+        }
+        (13, None) => DateTime::from_timestamp_millis(seconds.parse().ok()?),
+        _ => None,
+    }
+}
+
+// syslog timestamps without a year are in the previous year if they would be more than a day in the future
+fn syslog(word: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let mut parsed = Parsed::new();
+    parse(&mut parsed, word, StrftimeItems::new(SYSLOG_FORMAT)).ok()?;
+    let in_year = |year: i32| {
+        let mut parsed = parsed.clone();
+        parsed.set_year(year as i64).ok()?;
+        parsed.to_naive_datetime_with_offset(0).ok()
+    };
+    let timestamp = in_year(now.year())?.and_utc();
+    if timestamp > now + Duration::days(1) {
+        return in_year(now.year() - 1).map(|timestamp| timestamp.and_utc());
+    }
+
+    Some(timestamp)
+}
+
+// resolves 'now', 'today', and 'yesterday' optionally followed by offsets such as '-1h' or '+30m', or offsets alone
+// relative to now; offsets are in s(econds), m(inutes), h(ours), d(ays), or w(eeks)
+pub fn relative(word: &str, now: &DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    let midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_local_timezone(*now.offset()).single();
+    let (mut instant, mut offsets) = if let Some(offsets) = word.strip_prefix("now") {
+        (*now, offsets)
+    } else if let Some(offsets) = word.strip_prefix("today") {
+        (midnight(now.date_naive())?, offsets)
+    } else if let Some(offsets) = word.strip_prefix("yesterday") {
+        (midnight(now.date_naive().pred_opt()?)?, offsets)
+    } else if word.starts_with(['+', '-']) {
+        (*now, word)
+    } else {
+        return None;
+    };
+
+    while !offsets.is_empty() {
+        let sign = match offsets.chars().next()? {
+            '+' => 1,
+            '-' => -1,
+            _ => return None,
+        };
+        let digits = offsets[1..].chars().take_while(|c| c.is_ascii_digit()).count();
+        let amount = sign * offsets[1..1 + digits].parse::<i64>().ok()?;
+        let offset = match offsets[1 + digits..].chars().next()? {
+            's' => Duration::try_seconds(amount)?,
+            'm' => Duration::try_minutes(amount)?,
+            'h' => Duration::try_hours(amount)?,
+            'd' => Duration::try_days(amount)?,
+            'w' => Duration::try_weeks(amount)?,
+            _ => return None,
+        };
+        instant = instant.checked_add_signed(offset)?;
+        offsets = &offsets[2 + digits..];
     }
+
+    Some(instant)
 }
 
 impl FromWord<()> for IpAddr {
@@ -131,19 +281,21 @@ impl FromWord<()> for VersionReq {
 #[derive(Debug, PartialEq)]
 pub struct Term<T> {
     pub position: Position,
+    pub span: usize,
     pub value: T,
 }
 
 pub struct Parser<T, F>(PhantomData<T>, PhantomData<F>);
 
 impl<T: FromWord<F>, F> Parser<T, F> {
-    pub fn from_tokens(tokens: &Vec<Token>, format: &F) -> Vec<Term<T>> {
+    pub fn from_tokens(tokens: &[Token], format: &F) -> Vec<Term<T>> {
         let mut result = Vec::new();
         for token in tokens {
             if !token.separator {
                 if let Ok(value) = T::from_word(token.word, format) {
                     result.push(Term {
                         position: token.position,
+                        span: 1,
                         value: value,
                     });
                 }
@@ -151,6 +303,82 @@ impl<T: FromWord<F>, F> Parser<T, F> {
         }
         return result;
     }
+
+    // joins a token with the following separators and tokens if the separators are literals of the format, the
+    // longest span which parses is used and its tokens are not parsed again
+    pub fn from_joined_tokens(tokens: &[Token], format: &F, literals: &str) -> Vec<Term<T>> {
+        if literals.is_empty() {
+            return Parser::<T, F>::from_tokens(tokens, format);
+        }
+
+        let mut result = Vec::new();
+        let mut ends = Vec::new();
+        let mut word = String::new();
+        let mut index = 0;
+        while index < tokens.len() {
+            if tokens[index].separator || !T::starts(tokens[index].word, format) {
+                index += 1;
+                continue;
+            }
+
+            Parser::<T, F>::span_ends(tokens, index, literals, &mut ends);
+            for &end in ends.iter().rev() {
+                word.clear();
+                tokens[index..=end].iter().for_each(|token| word.push_str(token.word));
+                if let Ok(value) = T::from_word(&word, format) {
+                    result.push(Term {
+                        position: tokens[index].position,
+                        span: end - index + 1,
+                        value: value,
+                    });
+                    index = end;
+                    break;
+                }
+            }
+            index += 1;
+        }
+
+        return result;
+    }
+
+    // collects the indexes of the tokens a span starting at the index can end with, at most one per literal
+    fn span_ends(tokens: &[Token], index: usize, literals: &str, ends: &mut Vec<usize>) {
+        let joinable = |word: &str| {
+            word.chars().all(|character| {
+                literals.contains(character) || (character.is_whitespace() && literals.contains(char::is_whitespace))
+            })
+        };
+
+        ends.clear();
+        ends.push(index);
+        let mut next = index + 1;
+        while ends.len() <= literals.chars().count() {
+            let mut end = next;
+            while end < tokens.len() && tokens[end].separator && joinable(tokens[end].word) {
+                end += 1;
+            }
+            if end == next || end == tokens.len() || tokens[end].separator {
+                break;
+            }
+            ends.push(end);
+            next = end + 1;
+        }
+    }
+}
+
+// returns the literal characters of chrono format strings which may be separators
+pub fn literals(formats: &[String]) -> String {
+    formats
+        .iter()
+        .flat_map(|format| StrftimeItems::new(format))
+        .filter_map(|item| match item {
+            Item::Literal(literal) | Item::Space(literal) => Some(literal.to_string()),
+            Item::OwnedLiteral(literal) | Item::OwnedSpace(literal) => Some(literal.to_string()),
+            _ => None,
+        })
+        .flat_map(|literal| literal.chars().collect::<Vec<char>>())
+        .filter(|character| !character.is_alphanumeric())
+        .collect()
 }
 
 #[cfg(test)]
@@ -225,15 +453,16 @@ mod value_tests {
     }
 
     #[test]
+    #[allow(deprecated, clippy::zero_prefixed_literal)]
     fn new_date() {
         // setup
         let format = "%F";
         let date = NaiveDate::from_ymd(2021, 01, 01);
 
         // exercise
-        let ok_1 = NaiveDate::from_word(&date.format(format).to_string(), &String::from(format));
-        let err_1 = NaiveDate::from_word("5.5", &String::from(format));
-        let err_2 = NaiveDate::from_word("08/08/2021", &String::from(format));
+        let ok_1 = NaiveDate::from_word(&date.format(format).to_string(), &vec![String::from(format)]);
+        let err_1 = NaiveDate::from_word("5.5", &vec![String::from(format)]);
+        let err_2 = NaiveDate::from_word("08/08/2021", &vec![String::from(format)]);
 
         // verify
         assert_eq!(date, ok_1.unwrap());
@@ -242,15 +471,37 @@ mod value_tests {
     }
 
     #[test]
+    fn new_date_formats() {
+        // setup
+        let formats = vec![String::from("%F"), String::from("%d/%m/%Y"), String::from("%m/%d/%Y")];
+        let date = NaiveDate::from_ymd_opt(2021, 8, 9).unwrap();
+
+        // exercise
+        let ok_1 = NaiveDate::from_word("2021-08-09", &formats);
+        let ok_2 = NaiveDate::from_word("09/08/2021", &formats);
+        let ok_3 = NaiveDate::from_word("08/31/2021", &formats);
+        let err_1 = NaiveDate::from_word("2021.08.09", &formats);
+        let err_2 = NaiveDate::from_word("2021-08-09", &vec![]);
+
+        // verify
+        assert_eq!(date, ok_1.unwrap());
+        assert_eq!(date, ok_2.unwrap());
+        assert_eq!(NaiveDate::from_ymd_opt(2021, 8, 31).unwrap(), ok_3.unwrap());
+        assert_eq!(true, err_1.is_err());
+        assert_eq!(true, err_2.is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
     fn new_time() {
         // setup
         let format = "%T";
         let time = NaiveTime::from_hms(15, 15, 15);
 
         // exercise
-        let ok_1 = NaiveTime::from_word(&time.format(format).to_string(), &String::from(format));
-        let err_1 = NaiveTime::from_word("5.5", &String::from(format));
-        let err_2 = NaiveTime::from_word("15.15.15", &String::from(format));
+        let ok_1 = NaiveTime::from_word(&time.format(format).to_string(), &vec![String::from(format)]);
+        let err_1 = NaiveTime::from_word("5.5", &vec![String::from(format)]);
+        let err_2 = NaiveTime::from_word("15.15.15", &vec![String::from(format)]);
 
         // verify
         assert_eq!(time, ok_1.unwrap());
@@ -259,6 +510,7 @@ mod value_tests {
     }
 
     #[test]
+    #[allow(clippy::needless_borrow)]
     fn new_date_time() {
         // setup
         let format = "%+";
@@ -266,9 +518,9 @@ mod value_tests {
         let date_time = DateTime::parse_from_str(date_time_string, format).unwrap();
 
         // exercise
-        let ok_1 = DateTime::<FixedOffset>::from_word(&date_time_string, &String::from(format));
-        let err_1 = DateTime::<FixedOffset>::from_word("5.5", &String::from(format));
-        let err_2 = DateTime::<FixedOffset>::from_word("2001-07-08 00:34:60", &String::from(format));
+        let ok_1 = DateTime::<FixedOffset>::from_word(&date_time_string, &vec![String::from(format)]);
+        let err_1 = DateTime::<FixedOffset>::from_word("5.5", &vec![String::from(format)]);
+        let err_2 = DateTime::<FixedOffset>::from_word("2001-07-08 00:34:60", &vec![String::from(format)]);
 
         // verify
         assert_eq!(date_time, ok_1.unwrap());
@@ -277,6 +529,7 @@ mod value_tests {
     }
 
     #[test]
+    #[allow(clippy::needless_borrow)]
     fn new_local_date_time() {
         // setup
         let format = "%Y-%m-%dT%H:%M:%S%.f";
@@ -284,9 +537,9 @@ mod value_tests {
         let date_time = NaiveDateTime::parse_from_str(date_time_string, format).unwrap();
 
         // exercise
-        let ok_1 = NaiveDateTime::from_word(&date_time_string, &String::from(format));
-        let err_1 = NaiveDateTime::from_word("5.5", &String::from(format));
-        let err_2 = NaiveDateTime::from_word("2001-07-08 00:34:60", &String::from(format));
+        let ok_1 = NaiveDateTime::from_word(&date_time_string, &vec![String::from(format)]);
+        let err_1 = NaiveDateTime::from_word("5.5", &vec![String::from(format)]);
+        let err_2 = NaiveDateTime::from_word("2001-07-08 00:34:60", &vec![String::from(format)]);
 
         // verify
         assert_eq!(date_time, ok_1.unwrap());
@@ -294,6 +547,109 @@ mod value_tests {
         assert_eq!(true, err_2.is_err());
     }
 
+    #[test]
+    fn new_timestamp() {
+        // setup
+        let timestamp = DateTime::parse_from_rfc3339("2026-10-16T19:41:07Z").unwrap().to_utc();
+        let timestamps = vec![
+            "2026-10-16T21:41:07+02:00",
+            "Fri, 16 Oct 2026 21:41:07 +0200",
+            "16/Oct/2026:21:41:07 +0200",
+            "[16/Oct/2026:21:41:07 +0200]",
+            "1792179667",
+        ];
+
+        // exercise & verify
+        for timestamp_string in timestamps {
+            assert_eq!(
+                timestamp,
+                DateTime::<Utc>::from_word(timestamp_string, &timestamp).unwrap()
+            );
+        }
+        assert_eq!(
+            timestamp + Duration::milliseconds(250),
+            DateTime::<Utc>::from_word("1792179667.25", &timestamp).unwrap()
+        );
+        assert_eq!(
+            timestamp + Duration::milliseconds(250),
+            DateTime::<Utc>::from_word("1792179667250", &timestamp).unwrap()
+        );
+        assert_eq!(
+            DateTime::parse_from_rfc3339("2026-10-12T00:00:00Z").unwrap().to_utc(),
+            DateTime::<Utc>::from_word("2026-W42-1", &timestamp).unwrap()
+        );
+        assert_eq!(
+            DateTime::parse_from_rfc3339("2025-10-17T12:00:00Z").unwrap().to_utc(),
+            DateTime::<Utc>::from_word("Oct 17 12:00:00", &(timestamp - Duration::days(365))).unwrap()
+        );
+        assert_eq!(true, DateTime::<Utc>::from_word("5.5", &timestamp).is_err());
+        assert_eq!(true, DateTime::<Utc>::from_word("179217966", &timestamp).is_err());
+        assert_eq!(true, DateTime::<Utc>::from_word("1792179667.", &timestamp).is_err());
+        assert_eq!(
+            true,
+            DateTime::<Utc>::from_word("2026-10-16 21:41:07", &timestamp).is_err()
+        );
+    }
+
+    #[test]
+    fn relative_literals() {
+        // setup
+        let now = DateTime::parse_from_rfc3339("2021-10-16T21:41:07+02:00").unwrap();
+        let at = |date_time: &str| Some(DateTime::parse_from_rfc3339(date_time).unwrap());
+
+        // exercise & verify
+        assert_eq!(at("2021-10-16T21:41:07+02:00"), relative("now", &now));
+        assert_eq!(at("2021-10-16T20:41:07+02:00"), relative("now-1h", &now));
+        assert_eq!(at("2021-10-16T21:26:07+02:00"), relative("-15m", &now));
+        assert_eq!(at("2021-10-23T21:41:37+02:00"), relative("+1w+30s", &now));
+        assert_eq!(at("2021-10-16T00:00:00+02:00"), relative("today", &now));
+        assert_eq!(at("2021-10-15T00:00:00+02:00"), relative("yesterday", &now));
+        assert_eq!(at("2021-10-14T12:00:00+02:00"), relative("yesterday-1d+12h", &now));
+        assert_eq!(None, relative("2021-10-16", &now));
+        assert_eq!(None, relative("now-", &now));
+        assert_eq!(None, relative("now-1", &now));
+        assert_eq!(None, relative("now-1y", &now));
+        assert_eq!(None, relative("-h", &now));
+        assert_eq!(None, relative("nowhere", &now));
+    }
+
+    #[test]
+    fn timestamp_starts() {
+        // setup
+        let now = DateTime::parse_from_rfc3339("2026-10-16T19:41:07Z").unwrap().to_utc();
+
+        // exercise & verify
+        assert_eq!(true, DateTime::<Utc>::starts("2026-10-16T21:41:07+02:00", &now));
+        assert_eq!(true, DateTime::<Utc>::starts("[16/Oct/2026", &now));
+        assert_eq!(true, DateTime::<Utc>::starts("Oct", &now));
+        assert_eq!(true, DateTime::<Utc>::starts("fri,", &now));
+        assert_eq!(false, DateTime::<Utc>::starts("host", &now));
+        assert_eq!(false, DateTime::<Utc>::starts("Oc", &now));
+        assert_eq!(false, DateTime::<Utc>::starts("é", &now));
+    }
+
+    #[test]
+    fn syslog_year() {
+        // setup
+        let now = DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z").unwrap().to_utc();
+
+        // exercise
+        let current = syslog("Jan  1 21:41:07", now);
+        let previous = syslog("Dec 31 21:41:07", now);
+        let invalid = syslog("Feb 30 21:41:07", now);
+
+        // verify
+        assert_eq!(
+            DateTime::parse_from_rfc3339("2026-01-01T21:41:07Z").unwrap().to_utc(),
+            current.unwrap()
+        );
+        assert_eq!(
+            DateTime::parse_from_rfc3339("2025-12-31T21:41:07Z").unwrap().to_utc(),
+            previous.unwrap()
+        );
+        assert_eq!(None, invalid);
+    }
+
     #[test]
     fn new_ip_address() {
         // setup
@@ -495,6 +851,7 @@ mod value_tests {
 #[cfg(test)]
 mod term_tests {
     use super::*;
+    use crate::tokenizer::{Separators, Tokenizer, WHITESPACES};
 
     #[test]
     fn from_separator() {
@@ -539,6 +896,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position,
+                span: 1,
                 value: Id::from_word(word, &()).unwrap(),
             },
             id_terms.get(0).unwrap()
@@ -571,6 +929,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position,
+                span: 1,
                 value: Id::from_word(word, &()).unwrap(),
             },
             id_terms.get(0).unwrap()
@@ -580,6 +939,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position,
+                span: 1,
                 value: i64::from_word(word, &()).unwrap(),
             },
             integer_terms.get(0).unwrap()
@@ -589,6 +949,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position,
+                span: 1,
                 value: f64::from_word(word, &()).unwrap(),
             },
             float_terms.get(0).unwrap()
@@ -617,6 +978,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position,
+                span: 1,
                 value: Id::from_word(word, &()).unwrap(),
             },
             id_terms.get(0).unwrap()
@@ -628,6 +990,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position,
+                span: 1,
                 value: f64::from_word(word, &()).unwrap(),
             },
             float_terms.get(0).unwrap()
@@ -703,6 +1066,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position0,
+                span: 1,
                 value: Id::from_word(word0, &()).unwrap(),
             },
             id_terms.get(0).unwrap()
@@ -710,6 +1074,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position2,
+                span: 1,
                 value: Id::from_word(word2, &()).unwrap(),
             },
             id_terms.get(1).unwrap()
@@ -717,6 +1082,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position4,
+                span: 1,
                 value: Id::from_word(word4, &()).unwrap(),
             },
             id_terms.get(2).unwrap()
@@ -724,6 +1090,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position6,
+                span: 1,
                 value: Id::from_word(word6, &()).unwrap(),
             },
             id_terms.get(3).unwrap()
@@ -731,6 +1098,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position8,
+                span: 1,
                 value: Id::from_word(word8, &()).unwrap(),
             },
             id_terms.get(4).unwrap()
@@ -798,6 +1166,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position2,
+                span: 1,
                 value: i64::from_word(word2, &()).unwrap(),
             },
             integer_terms.get(0).unwrap()
@@ -867,6 +1236,7 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position2,
+                span: 1,
                 value: f64::from_word(word2, &()).unwrap(),
             },
             float_terms.get(0).unwrap()
@@ -874,9 +1244,40 @@ mod term_tests {
         assert_eq!(
             &Term {
                 position: position8,
+                span: 1,
                 value: f64::from_word(word8, &()).unwrap(),
             },
             float_terms.get(1).unwrap()
         );
     }
+
+    #[test]
+    fn from_joined_tokens() {
+        // setup
+        let separators = Separators::new(vec![WHITESPACES, ":"]).unwrap();
+        let tokenizer = Tokenizer::new(separators).unwrap();
+        let tokens = tokenizer.tokens("at Oct  6 21:41:07 and Oct 16 99:41:07");
+        let formats = vec![String::from("%b %e %H:%M:%S")];
+
+        // exercise
+        let time_terms = Parser::<NaiveTime, Vec<String>>::from_joined_tokens(&tokens, &formats, &literals(&formats));
+
+        // verify
+        assert_eq!(
+            vec![Term {
+                position: 2,
+                span: 10,
+                value: NaiveTime::from_hms_opt(21, 41, 7).unwrap(),
+            }],
+            time_terms
+        );
+    }
+
+    #[test]
+    fn format_literals() {
+        assert_eq!("  ::", literals(&[String::from("%b %e %H:%M:%S")]));
+        assert_eq!("--", literals(&[String::from("%F")]));
+        assert_eq!("", literals(&[String::from("%Y%m%dT%H%M%S")]));
+        assert_eq!("--//", literals(&[String::from("%F"), String::from("%d/%m/%Y")]));
+    }
 }
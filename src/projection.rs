@@ -0,0 +1,67 @@
+use std::fmt::Display;
+
+use crate::evaluator::Variable;
+use crate::filter::Format;
+use crate::tokenizer::Token;
+
+pub type Values = Box<dyn Fn(&[Token]) -> Vec<String>>;
+
+pub struct Projection {
+    values: Values,
+}
+
+impl Projection {
+    pub fn new<T: Display + 'static>(variable: Variable<T>) -> Self {
+        Projection {
+            values: Box::new(move |tokens: &[Token]| {
+                variable
+                    .terms(tokens)
+                    .into_iter()
+                    .map(|term| term.value.to_string())
+                    .collect()
+            }),
+        }
+    }
+
+    pub fn values(&self, tokens: &[Token]) -> Vec<String> {
+        (self.values)(tokens)
+    }
+}
+
+// joins the values of a row, CSV values containing commas, quotes, or line breaks are quoted
+pub fn join(values: &[String], format: &Format) -> String {
+    match format {
+        Format::Csv => values
+            .iter()
+            .map(|value| quote(value))
+            .collect::<Vec<String>>()
+            .join(","),
+        _ => values.join("\t"),
+    }
+}
+
+fn quote(value: &str) -> String {
+    match value.contains([',', '"', '\r', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod projection_tests {
+    use super::*;
+
+    #[test]
+    fn join_values() {
+        // setup
+        let values = vec![String::from("a"), String::from("b,c"), String::from("d \"e\"")];
+
+        // exercise
+        let tsv = join(&values, &Format::Tsv);
+        let csv = join(&values, &Format::Csv);
+
+        // verify
+        assert_eq!("a\tb,c\td \"e\"", tsv);
+        assert_eq!("a,\"b,c\",\"d \"\"e\"\"\"", csv);
+    }
+}
@@ -0,0 +1,233 @@
+use regex::bytes::Regex;
+use regex_syntax::ParserBuilder;
+use std::io::{BufRead, BufReader, Read, Result};
+
+pub enum RecordSeparator {
+    Newline,
+    Null,
+    Pattern(Regex),
+}
+
+// splits input into records; the terminator is the separator which followed the last record read, which is empty if
+// the input ends without a separator, and is written after the record on output
+pub struct Records<'a> {
+    reader: BufReader<&'a mut dyn Read>,
+    separator: &'a RecordSeparator,
+    pending: Vec<u8>,
+    searched: usize,
+    overlap: Option<usize>,
+    terminator: Vec<u8>,
+    last_separator: Vec<u8>,
+}
+
+impl<'a> Records<'a> {
+    pub fn new(read: &'a mut dyn Read, separator: &'a RecordSeparator) -> Self {
+        let last_separator = match separator {
+            RecordSeparator::Null => b"\0".to_vec(),
+            _ => b"\n".to_vec(),
+        };
+        // a separator which is not yet complete starts at most its maximum length before the end of the pending bytes
+        let overlap = match separator {
+            RecordSeparator::Pattern(regex) => ParserBuilder::new()
+                .utf8(false)
+                .build()
+                .parse(regex.as_str())
+                .ok()
+                .and_then(|hir| hir.properties().maximum_len()),
+            _ => None,
+        };
+
+        Records {
+            reader: BufReader::new(read),
+            separator,
+            pending: Vec::new(),
+            searched: 0,
+            overlap,
+            terminator: Vec::new(),
+            last_separator,
+        }
+    }
+
+    // reads the next record without its separator into the buffer and returns the number of bytes consumed
+    pub fn read(&mut self, buffer: &mut Vec<u8>) -> Result<usize> {
+        buffer.clear();
+        self.terminator.clear();
+        let length = match self.separator {
+            RecordSeparator::Newline => {
+                let length = self.reader.read_until(b'\n', buffer)?;
+                if buffer.ends_with(b"\n") {
+                    buffer.pop();
+                    if buffer.ends_with(b"\r") {
+                        buffer.pop();
+                    }
+                    self.terminator.push(b'\n');
+                }
+                length
+            }
+            RecordSeparator::Null => {
+                let length = self.reader.read_until(b'\0', buffer)?;
+                if buffer.ends_with(b"\0") {
+                    buffer.pop();
+                    self.terminator.push(b'\0');
+                }
+                length
+            }
+            RecordSeparator::Pattern(regex) => self.read_pattern(regex, buffer)?,
+        };
+
+        if !self.terminator.is_empty() {
+            self.last_separator.clone_from(&self.terminator);
+        }
+        Ok(length)
+    }
+
+    // returns the first buffered bytes of the input without consuming them, before the first record is read
+    pub fn peek(&mut self) -> Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    pub fn terminator(&self) -> &[u8] {
+        &self.terminator
+    }
+
+    // the last separator read, or the default separator before one is read, separates output lines which are not
+    // copies of records such as matched tokens
+    pub fn last_separator(&self) -> &[u8] {
+        &self.last_separator
+    }
+
+    // a separator which ends at the end of the pending bytes is only accepted at the end of the input, as it may
+    // continue in the bytes read next; the search resumes where a separator of bounded length may still start, a
+    // separator of unbounded length is searched from the start of the pending bytes again
+    fn read_pattern(&mut self, regex: &Regex, buffer: &mut Vec<u8>) -> Result<usize> {
+        loop {
+            let available = self.reader.fill_buf()?;
+            let end_of_input = available.is_empty();
+            let separator = regex.find_at(&self.pending, self.searched);
+            if let Some(separator) = separator {
+                if separator.end() < self.pending.len() || end_of_input {
+                    buffer.extend_from_slice(&self.pending[..separator.start()]);
+                    self.terminator.extend_from_slice(separator.as_bytes());
+                    let length = separator.end();
+                    self.pending.drain(..length);
+                    self.searched = 0;
+                    return Ok(length);
+                }
+            }
+
+            if end_of_input {
+                buffer.append(&mut self.pending);
+                self.searched = 0;
+                return Ok(buffer.len());
+            }
+
+            let start = separator.map_or(self.pending.len(), |separator| separator.start());
+            self.searched = match self.overlap {
+                Some(overlap) => start.min((self.pending.len() + 1).saturating_sub(overlap)),
+                None => 0,
+            };
+            let length = available.len();
+            self.pending.extend_from_slice(available);
+            self.reader.consume(length);
+        }
+    }
+}
+
+#[cfg(test)]
+mod records_tests {
+    use super::*;
+
+    #[test]
+    fn newline_and_null() {
+        // setup
+        let separators = vec![
+            (RecordSeparator::Newline, "a 9\r\nb 1\nc", b"\n"),
+            (RecordSeparator::Null, "a 9\0b 1\0c", b"\0"),
+        ];
+
+        for (separator, input, terminator) in separators {
+            let mut input = input.as_bytes();
+            let mut records = Records::new(&mut input, &separator);
+
+            // exercise
+            let records = read_all(&mut records);
+
+            // verify
+            assert_eq!(
+                records,
+                vec![
+                    (String::from("a 9"), terminator.to_vec()),
+                    (String::from("b 1"), terminator.to_vec()),
+                    (String::from("c"), Vec::new())
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn pattern() {
+        // setup
+        let separator = RecordSeparator::Pattern(Regex::new(r"\n\n+|;;").unwrap());
+        let mut input = "a 9\nb 1\n\n\nc;;d\n\ne".as_bytes();
+        let mut records = Records::new(&mut input, &separator);
+
+        // exercise
+        let records = read_all(&mut records);
+
+        // verify
+        assert_eq!(
+            records,
+            vec![
+                (String::from("a 9\nb 1"), b"\n\n\n".to_vec()),
+                (String::from("c"), b";;".to_vec()),
+                (String::from("d"), b"\n\n".to_vec()),
+                (String::from("e"), Vec::new())
+            ]
+        );
+    }
+
+    #[test]
+    fn pattern_across_reads() {
+        // setup
+        let separators = vec![
+            (r";;", ["a 9;", ";b 1", ";", ";c"], b";;".to_vec()),
+            (r"\n\n+", ["a 9\n", "\nb 1", "\n\n", "\nc"], b"\n\n\n".to_vec()),
+        ];
+
+        for (pattern, chunks, terminator) in separators {
+            let separator = RecordSeparator::Pattern(Regex::new(pattern).unwrap());
+            let mut input = chunks[0]
+                .as_bytes()
+                .chain(chunks[1].as_bytes())
+                .chain(chunks[2].as_bytes())
+                .chain(chunks[3].as_bytes());
+            let mut records = Records::new(&mut input, &separator);
+
+            // exercise
+            let records = read_all(&mut records);
+
+            // verify
+            assert_eq!(
+                records,
+                vec![
+                    (String::from("a 9"), terminator[..2].to_vec()),
+                    (String::from("b 1"), terminator.clone()),
+                    (String::from("c"), Vec::new())
+                ]
+            );
+        }
+    }
+
+    fn read_all(records: &mut Records) -> Vec<(String, Vec<u8>)> {
+        let mut buffer = Vec::new();
+        let mut all = Vec::new();
+        while records.read(&mut buffer).unwrap() > 0 {
+            all.push((
+                String::from_utf8(buffer.clone()).unwrap(),
+                records.terminator().to_vec(),
+            ));
+        }
+
+        all
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::HashSet;
 
 pub type Position = usize;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Token<'a> {
     pub position: Position,
     pub separator: bool,
@@ -11,7 +11,7 @@ pub struct Token<'a> {
 }
 
 pub const WHITESPACES: &str = "[:space:]";
-pub const SEPARATORS: &'static [&'static str] = &[
+pub const SEPARATORS: &[&str] = &[
     " ", ",", ";", "|", "!", "\"", "#", "$", "%", "&", "'", "(", ")", "*", "+", "-", ".", "/", ":", "<", "=", ">", "?",
     "@", "[", "\\", "]", "^", "_", "`", "{", "}", "~",
 ];